@@ -1,5 +1,8 @@
 use crate::bitboard::Bitboard;
+use crate::bitboard::MDBitboard;
 use crate::bitboard::RawBitboard;
+use crate::bits::bitscan;
+use crate::coordinates::CoordinateSystem;
 use crate::coordinates::FileMajorCS;
 use crate::coordinates::AntidiagonalMajorCS;
 use crate::coordinates::ProdiagonalMajorCS;
@@ -9,11 +12,13 @@ use crate::gamestate::ChessGame;
 use crate::gamestate::Bitboards;
 use crate::grid::StandardCoordinate;
 use crate::laneutils::lanelimit;
+use crate::laneutils::lanescan_within;
 use crate::movegen::king::king_attack;
 use crate::piece::Color;
 use crate::piece::Species;
 use crate::movegen::knight::knight_attack;
 use crate::movegen::pawn::{pawn_attack, reverse_pawn_attack};
+use crate::rmrel::absolutize;
 use crate::rmrel::relativize;
 
 /// Determines if a hypothetical king placed on `vuln_sq` is currently
@@ -94,4 +99,51 @@ fn is_attacked_king(args: AttackQuery) -> bool {
     bb = args.board.class(args.board.active_player.oppo(), Species::King);
     bb &= king_attack(args.vuln_sq.into());
     return bb.is_not_empty();
-}
\ No newline at end of file
+}
+
+// # Attackers
+
+/// Locates the pieces of the given class which attack `target`. Only the pieces
+/// included in `occupancy` are considered, both as attackers and as obstructions,
+/// such that pieces hidden behind an attacker (x-rays) are revealed once it is removed
+/// from `occupancy`. Pins are not considered.
+pub fn locate_attackers(board: &Bitboards, occupancy: &MDBitboard, target: StandardCoordinate,
+    color: Color, species: Species) -> Bitboard<StandardCS>
+{
+    let mut bb: Bitboard<StandardCS> = Bitboard::empty();
+    match species {
+        Species::Pawn => {
+            let origins = reverse_pawn_attack(relativize(target, color));
+            for origin in bitscan(origins) { bb.set(absolutize(origin, color).into()); }
+        },
+        Species::Knight => bb = knight_attack(target.into()),
+        Species::King => bb = king_attack(target.into()),
+        Species::Bishop => {
+            bb |= locate_slide_attackers::<ProdiagonalMajorCS>(occupancy, target);
+            bb |= locate_slide_attackers::<AntidiagonalMajorCS>(occupancy, target);
+        },
+        Species::Rook => {
+            bb |= locate_slide_attackers::<RankMajorCS>(occupancy, target);
+            bb |= locate_slide_attackers::<FileMajorCS>(occupancy, target);
+        },
+        Species::Queen => {
+            bb |= locate_slide_attackers::<RankMajorCS>(occupancy, target);
+            bb |= locate_slide_attackers::<FileMajorCS>(occupancy, target);
+            bb |= locate_slide_attackers::<ProdiagonalMajorCS>(occupancy, target);
+            bb |= locate_slide_attackers::<AntidiagonalMajorCS>(occupancy, target);
+        }
+    }
+    bb &= board.class(color, species);
+    bb &= occupancy.get();
+    return bb;
+}
+
+/// Computes the squares from which a slider could reach `target` along its lane in `C`.
+fn locate_slide_attackers<C: CoordinateSystem>(occupancy: &MDBitboard,
+    target: StandardCoordinate) -> Bitboard<StandardCS>
+{
+    let lane: Bitboard<C> = lanescan_within(occupancy.get(), target);
+    let mut bb: Bitboard<StandardCS> = Bitboard::empty();
+    for coord in lane.scan() { bb.set(StandardCoordinate::from(coord).into()); }
+    return bb;
+}
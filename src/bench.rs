@@ -0,0 +1,59 @@
+//! A fixed-depth search over a standard set of positions. The total node count
+//! is deterministic, and so serves both as a signature for detecting unintended
+//! changes to the search and as a measure of the effectiveness of pruning.
+
+use crate::cache::Cache;
+use crate::cache::HashChars;
+use crate::cli::format_integer;
+use crate::gamestate::ChessGame;
+use crate::misc::SegVec;
+use crate::search::IterDeepSearchContext;
+use crate::search::iterdeep_search;
+use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use std::time::Instant;
+
+pub const BENCH_POSITIONS: [&str; 8] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+    "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+];
+
+#[derive(Clone, Copy)]
+pub struct BenchResult {
+    pub node_count: u64,
+    pub elapsed: Duration
+}
+
+/// Searches each of the [`BENCH_POSITIONS`] to the given depth with a fresh cache.
+/// The node count is reproducible only when searching with a single thread.
+pub fn bench(depth: u8, cache_mem_capacity: u64, thread_count: usize) -> BenchResult {
+    let hash_ch = HashChars::new([0; 32]);
+    let begin = Instant::now();
+    let mut node_count: u64 = 0;
+    for fen in BENCH_POSITIONS {
+        let mut gstate = ChessGame::from_fen(fen, hash_ch.clone()).unwrap();
+        let cache = Cache::new(cache_mem_capacity);
+        let result = iterdeep_search(IterDeepSearchContext { gstate: &mut gstate,
+            movebuf: SegVec::new(&mut RefCell::default()),
+            deadline: begin + Duration::from_secs(60 * 60 * 24), cache: &cache,
+            stop: &AtomicBool::new(false), max_depth: depth, thread_count,
+            time_manager: None, on_iteration: &mut |_| {} });
+        node_count += result.node_count;
+    }
+    return BenchResult { node_count, elapsed: begin.elapsed() };
+}
+
+pub fn print_bench(depth: u8, thread_count: usize) {
+    let result = bench(depth, 16, thread_count);
+    let nps = result.node_count as f64 / result.elapsed.as_secs_f64();
+    println!("Nodes: {}", format_integer(result.node_count));
+    println!("Time: {} ms", result.elapsed.as_millis());
+    println!("NPS: {}", format_integer(nps as u64));
+}
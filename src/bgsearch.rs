@@ -0,0 +1,91 @@
+//! Infrastructure for protocol front-ends which must remain responsive to
+//! their controller while the engine is thinking. Standard input is read on
+//! a dedicated thread and the search is conducted on another. Both report
+//! to the front-end through a single channel of [`DriverEvent`]s.
+
+use crate::cache::Cache;
+use crate::clock::TimeManager;
+use crate::gamestate::ChessGame;
+use crate::misc::SegVec;
+use crate::search::iterdeep_search;
+use crate::search::IterDeepSearchContext;
+use crate::search::IterDeepSearchResult;
+use std::cell::RefCell;
+use std::io::BufRead;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::Sender;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+pub enum DriverEvent {
+    /// A single line of input from the controller.
+    Input(String),
+    /// Standard input was closed by the controller.
+    InputClosed,
+    SearchComplete(/* search id */ u64, IterDeepSearchResult)
+}
+
+/// Forwards each line of standard input to `events` until the input is closed.
+pub fn spawn_input_reader(events: Sender<DriverEvent>) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if events.send(DriverEvent::Input(line)).is_err() { return; }
+        }
+        let _ = events.send(DriverEvent::InputClosed);
+    });
+}
+
+pub struct BackgroundSearch {
+    pub id: u64,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>
+}
+
+impl BackgroundSearch {
+    /// Asks the search to conclude as soon as possible. The result of the
+    /// most recently completed iteration is still delivered as a
+    /// [`DriverEvent::SearchComplete`].
+    pub fn stop(&self) { self.stop.store(true, Ordering::Relaxed); }
+
+    /// Stops the search and waits for the search thread to exit.
+    pub fn halt(self) {
+        self.stop();
+        self.handle.join().unwrap();
+    }
+}
+
+pub struct BackgroundSearchParams {
+    pub id: u64,
+    pub gstate: ChessGame,
+    pub deadline: Instant,
+    pub max_depth: u8,
+    pub cache: Arc<Cache>,
+    /// The number of threads which search concurrently, see [`iterdeep_search`].
+    pub thread_count: usize,
+    /// Decides when to stop a search timed by a game clock, see [`TimeManager`].
+    pub time_manager: Option<TimeManager>,
+    pub events: Sender<DriverEvent>
+}
+
+/// Begins searching for the best move in `params.gstate` on a new thread.
+/// `on_iteration` is invoked on the search thread after each completed iteration.
+pub fn spawn_search<F>(params: BackgroundSearchParams, mut on_iteration: F)
+-> BackgroundSearch
+where F: FnMut(&IterDeepSearchResult) + Send + 'static
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let BackgroundSearchParams { id, mut gstate, deadline, max_depth, cache, thread_count,
+        time_manager, events } = params;
+    let handle = std::thread::spawn(move || {
+        let result = iterdeep_search(IterDeepSearchContext { gstate: &mut gstate,
+            movebuf: SegVec::new(&mut RefCell::default()), deadline, cache: &cache,
+            stop: &thread_stop, max_depth, thread_count, time_manager,
+            on_iteration: &mut on_iteration });
+        let _ = events.send(DriverEvent::SearchComplete(id, result));
+    });
+    return BackgroundSearch { id, stop, handle };
+}
@@ -149,6 +149,12 @@ pub fn print_bitboard(bb: RawBitboard) {
 #[derive(Clone, Copy, Default, Eq, PartialEq)]
 pub struct MDBitboard { array: [RawBitboard; 4] }
 
+impl std::ops::BitOrAssign for MDBitboard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        for i in 0..self.array.len() { self.array[i] |= rhs.array[i]; }
+    }
+}
+
 impl MDBitboard {
     pub fn get<C: CoordinateSystem>(&self) -> Bitboard<C> {
         Bitboard::<C>::from_raw(self.array[C::INDEX])
@@ -1,89 +1,293 @@
 use crate::crights::CastlingRights;
 use crate::enpassant::is_enpassant_vuln;
+use crate::eval::is_mate_score;
 use crate::gamestate::ChessGame;
 use crate::grid::File;
 use crate::grid::StandardCoordinate;
 use crate::piece::Color;
 use crate::piece::Piece;
 use crate::piece::PieceGrid;
-use crate::mov::AnyMove;
+use crate::piece::Species;
+use crate::mov::EncodedMove;
 use rand::Rng;
 use rand::SeedableRng;
 use rand::rngs::StdRng;
 use rand::thread_rng;
 use std::ops::Index;
 use std::ops::IndexMut;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
 
 #[derive(Copy, Clone)]
 pub struct CacheValue {
-    pub bestmov_id: u8,
+    /// The best move found by the search. Since distinct positions may share a hash,
+    /// this move must be checked for legality before it is played.
+    pub bestmov: EncodedMove,
     pub score: i16,
 }
 
+/// Describes how the cached score relates to the true score of the position.
+/// Only a search which completes within its window produces an exact score.
+/// A search which fails high produces a lower bound, and a search which
+/// fails low produces an upper bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoreBound { Exact, Lower, Upper }
+
 #[derive(Clone, Copy)]
 struct InternalCacheEntry {
     pub value: CacheValue,
+    pub bound: ScoreBound,
     pub depth: u8,
+    /// The generation of the search which last wrote or refreshed this entry.
+    pub generation: u8,
     pub hash: u64
 }
 
-pub struct Cache { vec: Vec<Option<InternalCacheEntry>> }
+// # Lock-free Slots
+//
+// The cache is shared by every thread of a multi-threaded search, without locking.
+// Each entry is packed into a single 64-bit word, which is stored alongside the hash
+// of the position XORed with that word. Concurrent writes to the same slot may
+// interleave such that the two words come from different entries, but then the hash
+// recovered from the slot no longer matches either position, and so a torn entry
+// is simply never found.
+
+const OCCUPIED_FLAG: u64 = 1 << 63;
+
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64
+}
+
+impl Slot {
+    fn load(&self) -> Option<InternalCacheEntry> {
+        let key = self.key.load(Ordering::Relaxed);
+        let data = self.data.load(Ordering::Relaxed);
+        if data & OCCUPIED_FLAG == 0 { return None; }
+        return Some(unpack_entry(key ^ data, data));
+    }
+
+    fn store(&self, entry: InternalCacheEntry) {
+        let data = pack_entry(&entry);
+        self.key.store(entry.hash ^ data, Ordering::Relaxed);
+        self.data.store(data, Ordering::Relaxed);
+    }
+
+    fn clear(&self) {
+        self.key.store(0, Ordering::Relaxed);
+        self.data.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Packs everything but the hash into a single word. Bits 0-15 hold the best move,
+/// bits 16-31 the score, bits 32-39 the depth, bits 40-47 the generation and
+/// bits 48-49 the bound.
+fn pack_entry(entry: &InternalCacheEntry) -> u64 {
+    let bound: u64 = match entry.bound {
+        ScoreBound::Exact => 0,
+        ScoreBound::Lower => 1,
+        ScoreBound::Upper => 2
+    };
+    return OCCUPIED_FLAG
+        | u64::from(entry.value.bestmov.bits())
+        | (u64::from(entry.value.score as u16) << 16)
+        | (u64::from(entry.depth) << 32)
+        | (u64::from(entry.generation) << 40)
+        | (bound << 48);
+}
+
+fn unpack_entry(hash: u64, data: u64) -> InternalCacheEntry {
+    let bound = match (data >> 48) & 0b11 {
+        0 => ScoreBound::Exact,
+        1 => ScoreBound::Lower,
+        _ => ScoreBound::Upper
+    };
+    let value = CacheValue {
+        bestmov: EncodedMove::from_bits(data as u16),
+        score: (data >> 16) as u16 as i16
+    };
+    return InternalCacheEntry { value, bound, depth: (data >> 32) as u8,
+        generation: (data >> 40) as u8, hash };
+}
+
+/// The number of entries which share a single hash key. A position may be stored
+/// in any entry of its bucket.
+const BUCKET_LEN: usize = 4;
+
+type Bucket = [Slot; BUCKET_LEN];
+
+/// The transposition table. Every operation takes `&self`, so that a single cache
+/// can be shared by every thread of a search, see [`crate::search::iterdeep_search`].
+pub struct Cache {
+    vec: Vec<Bucket>,
+    /// Incremented at the beginning of each search so that entries left behind by
+    /// earlier searches can be told apart and preferentially replaced.
+    generation: AtomicU8
+}
 
 impl Cache {
     pub fn new(mem_capacity: u64) -> Self {
-        let ewidth = u64::try_from(std::mem::size_of::<InternalCacheEntry>())
+        let bwidth = u64::try_from(std::mem::size_of::<Bucket>())
             .unwrap();
-        let len = (mem_capacity * u64::pow(2, 20)) / ewidth;
-        Self { vec: vec![None; usize::try_from(len).unwrap()] }
+        let len = ((mem_capacity * u64::pow(2, 20)) / bwidth).max(1);
+        let vec = (0..len).map(|_| Bucket::default()).collect();
+        Self { vec, generation: AtomicU8::new(0) }
     }
 
-    pub fn lookup_score_atleast(&self, state: &ChessGame, depth: u8) -> Option<i16>
+    /// Looks up the score of a search at least as deep as `depth`, provided that the
+    /// score is conclusive for a search with the window `(alpha, beta)`. An exact
+    /// score is always conclusive, whereas a lower bound is only conclusive if it
+    /// is at least `beta`, and an upper bound is only conclusive if it is at most `alpha`.
+    /// The `ply` is the distance of the position from the root of the current search.
+    pub fn lookup_score(&self, state: &ChessGame, depth: u8, ply: u8, alpha: i16, beta: i16)
+    -> Option<i16>
     {
-        let value = self.lookup_atleast(state, depth)?;
-        return Some(value.score);
+        let entry = self.lookup_entry(state)?;
+        if entry.depth < depth { return None; }
+        let score = unanchor_score(entry.value.score, ply);
+        let is_conclusive = match entry.bound {
+            ScoreBound::Exact => true,
+            ScoreBound::Lower => score >= beta,
+            ScoreBound::Upper => score <= alpha,
+        };
+        if !is_conclusive { return None; }
+        return Some(score);
     }
 
+    /// Looks up the best move and score of a search at least as deep as `depth`.
+    /// A mate score is given relative to the position itself, rather than to the
+    /// root of any search.
     pub fn lookup_atleast(&self, state: &ChessGame, depth: u8) -> Option<CacheValue> {
-        let lut_key = usize::try_from(state.hash.value() % 
-            u64::try_from(self.vec.len()).unwrap()).unwrap();
-        if let Some(entry) = self.vec[lut_key] {
-            if entry.hash == state.hash.value() {
-                if entry.depth >= depth {
-                    return Some(entry.value)
-                }
-            }
-        }
-        return None;
+        let entry = self.lookup_entry(state)?;
+        if entry.depth < depth { return None; }
+        return Some(entry.value);
     }
 
     pub fn lookup_any(&self, state: &ChessGame) -> Option<CacheValue> {
         self.lookup_atleast(state, 0)
     }
 
-    pub fn update(&mut self, state: &ChessGame, depth: u8, value: CacheValue) {
-        if self.lookup_atleast(state, depth).is_some() { return; }
-        let lut_key = usize::try_from(state.hash.value() % 
+    fn lookup_entry(&self, state: &ChessGame) -> Option<InternalCacheEntry> {
+        return self.vec[self.bucket_key(state)].iter()
+            .filter_map(Slot::load)
+            .find(|entry| entry.hash == state.hash.value());
+    }
+
+    fn bucket_key(&self, state: &ChessGame) -> usize {
+        return usize::try_from(state.hash.value() %
             u64::try_from(self.vec.len()).unwrap()).unwrap();
-        self.vec[lut_key] = Some(InternalCacheEntry { depth,
-            hash: state.hash.value(), value });
     }
+
+    /// Forgets every entry, for instance at the start of a new game.
+    pub fn clear(&self) {
+        for slot in self.vec.iter().flatten() { slot.clear(); }
+        self.generation.store(0, Ordering::Relaxed);
+    }
+
+    /// Marks the beginning of a new search. Entries written by previous searches
+    /// remain usable but become candidates for replacement.
+    pub fn new_generation(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the result of a search of the given depth. An existing entry for the same
+    /// position is kept, and refreshed, if it was searched deeper, or equally deep with an
+    /// exact score. Otherwise the entry is written into an empty slot of the bucket,
+    /// or else over the entry whose depth is smallest once discounted by its age.
+    /// The `ply` is the distance of the position from the root of the current search.
+    pub fn update(&self, state: &ChessGame, depth: u8, ply: u8, bound: ScoreBound,
+        mut value: CacheValue)
+    {
+        value.score = anchor_score(value.score, ply);
+        let generation = self.generation.load(Ordering::Relaxed);
+        let hash = state.hash.value();
+        let bucket = &self.vec[self.bucket_key(state)];
+        let new_entry = InternalCacheEntry { depth, bound, generation, hash, value };
+
+        let existing = bucket.iter()
+            .find_map(|slot| slot.load().filter(|e| e.hash == hash).map(|e| (slot, e)));
+        if let Some((slot, mut existing)) = existing {
+            let is_downgrade = (existing.bound == ScoreBound::Exact)
+                & (bound != ScoreBound::Exact);
+            let is_better = (existing.depth > depth)
+                | ((existing.depth == depth) & is_downgrade);
+            if is_better {
+                existing.generation = generation;
+                slot.store(existing);
+                return;
+            }
+            slot.store(new_entry);
+            return;
+        }
+
+        let victim = bucket.iter()
+            .min_by_key(|slot| match slot.load() {
+                None => i32::MIN,
+                Some(entry) => replacement_priority(&entry, generation)
+            })
+            .unwrap();
+        victim.store(new_entry);
+    }
+
+    /// Estimates the occupancy of the cache in permille, counting only entries which
+    /// were written during the current generation, as reported by UCI's `hashfull`.
+    pub fn hashfull(&self) -> u16 {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let sample: Vec<Option<InternalCacheEntry>> = self.vec.iter()
+            .flatten()
+            .take(1000)
+            .map(Slot::load)
+            .collect();
+        let used = sample.iter()
+            .filter(|slot| slot.is_some_and(|e| e.generation == generation))
+            .count();
+        return u16::try_from(used * 1000 / sample.len()).unwrap();
+    }
+}
+
+/// Converts a mate score relative to the root of the search into one relative to the
+/// position `ply` plies from the root, since the position may be reached at a different
+/// distance from the root of a later search.
+fn anchor_score(score: i16, ply: u8) -> i16 {
+    if !is_mate_score(score) { return score; }
+    if score > 0 { return score + i16::from(ply); }
+    return score - i16::from(ply);
+}
+
+/// The inverse of [`anchor_score`].
+fn unanchor_score(score: i16, ply: u8) -> i16 {
+    if !is_mate_score(score) { return score; }
+    if score > 0 { return score - i16::from(ply); }
+    return score + i16::from(ply);
+}
+
+/// Entries with a lower priority are replaced first. Each generation of age
+/// costs an entry the equivalent of a few plies of depth.
+fn replacement_priority(entry: &InternalCacheEntry, generation: u8) -> i32 {
+    let age = i32::from(generation.wrapping_sub(entry.generation));
+    return i32::from(entry.depth) - 4 * age;
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct IncrementalHash { 
     value: u64,
+    /// The hash of the placement of the pawns alone, see [`crate::pawn_eval::PawnCache`].
+    pawn_value: u64,
     chs: HashChars
 }
 
 impl IncrementalHash {
     pub fn new(chs: HashChars) -> Self {
-        Self { value: 0, chs }
+        Self { value: 0, pawn_value: 0, chs }
     }
     
     pub fn toggle_tile(&mut self, pos: StandardCoordinate, piece: Piece) {
         let lut_key = usize::from(pos.index()) * 12 + usize::from(piece.index());
         let ch = self.chs.piece_placements[lut_key];
         self.value ^= ch;
+        if piece.species() == Species::Pawn { self.pawn_value ^= ch; }
     }
 
     pub fn toggle_active(&mut self) {
@@ -104,6 +308,7 @@ impl IncrementalHash {
     }
 
     pub fn value(&self) -> u64 { self.value }
+    pub fn pawn_value(&self) -> u64 { self.pawn_value }
 }
 
 #[derive(Clone, PartialEq, Eq)]
@@ -0,0 +1,331 @@
+//! A front-end for the Chess Engine Communication Protocol (version 2),
+//! the protocol spoken by XBoard, WinBoard and related tooling.
+
+use crate::bgsearch::BackgroundSearch;
+use crate::bgsearch::BackgroundSearchParams;
+use crate::bgsearch::DriverEvent;
+use crate::bgsearch::spawn_input_reader;
+use crate::bgsearch::spawn_search;
+use crate::cache::Cache;
+use crate::cache::HashChars;
+use crate::clock::Clock;
+use crate::clock::TimeManager;
+use crate::clock::calc_time_limits;
+use crate::eval::to_centipawns;
+use crate::gamestate::ChessGame;
+use crate::gamestate::GameResult;
+use crate::gamestate::GameStatus;
+use crate::gamestate::status;
+use crate::lan::format_lan;
+use crate::lan::format_lan_line;
+use crate::lan::parse_lan;
+use crate::makemove::make_move;
+use crate::makemove::unmake_move;
+#[cfg(feature = "nnue")]
+use crate::nnue::load_network;
+#[cfg(feature = "nnue")]
+use crate::nnue::refresh_accumulator;
+#[cfg(feature = "nnue")]
+use crate::nnue::set_nnue_enabled;
+use crate::piece::Color;
+use crate::repetitions::count_repetitions;
+use crate::search::IterDeepSearchResult;
+use crate::stdinit::std_chess_position;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+use std::time::Instant;
+
+const HASH_MB: u64 = 64;
+
+/// The think time used when the controller has not told us anything about the clock.
+const DEFAULT_THINK_TIME: Duration = Duration::from_secs(5);
+
+/// Reads CECP commands from standard input and answers them on standard output
+/// until the controller sends `quit` or closes the input. The `greeting` is the
+/// first command, which has already been consumed by the caller.
+pub fn cecp_main(greeting: &str) {
+    let (events, receiver) = channel();
+    spawn_input_reader(events.clone());
+    let mut engine = CecpEngine::new(events);
+    if !engine.handle_command(greeting) { return; }
+    for event in receiver {
+        match event {
+            DriverEvent::Input(line) => if !engine.handle_command(&line) { break; },
+            DriverEvent::InputClosed => break,
+            DriverEvent::SearchComplete(id, result) => engine.handle_search_complete(id, result),
+        }
+    }
+    engine.halt_search();
+}
+
+struct CecpEngine {
+    gstate: ChessGame,
+    hash_ch: HashChars,
+    cache: Arc<Cache>,
+    /// The number of threads to search with, as established by `cores`.
+    thread_count: usize,
+    events: Sender<DriverEvent>,
+    search: Option<BackgroundSearch>,
+    next_search_id: u64,
+    /// In force mode the engine plays neither side and only tracks the moves it is given.
+    force: bool,
+    engine_color: Color,
+    /// Whether thinking output should be emitted during the search.
+    post: bool,
+    /// Moves per time control session, where zero denotes an incremental clock.
+    moves_per_session: u32,
+    increment: Duration,
+    /// The fixed think time per move, as established by `st`.
+    fixed_think_time: Option<Duration>,
+    max_depth: u8,
+    /// The time remaining on the engine's clock, as last reported by `time`, or
+    /// else the base time established by `level`.
+    clock: Option<Duration>
+}
+
+impl CecpEngine {
+    fn new(events: Sender<DriverEvent>) -> Self {
+        let hash_ch = HashChars::new_random();
+        Self {
+            gstate: std_chess_position(hash_ch.clone()),
+            hash_ch,
+            cache: Arc::new(Cache::new(HASH_MB)),
+            thread_count: 1,
+            events,
+            search: None,
+            next_search_id: 0,
+            force: false,
+            engine_color: Color::Black,
+            post: false,
+            moves_per_session: 0,
+            increment: Duration::ZERO,
+            fixed_think_time: None,
+            max_depth: u8::MAX,
+            clock: None
+        }
+    }
+
+    /// Processes a single line of input. Returns `false` once the
+    /// controller has asked the engine to quit.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(command) = tokens.first() else { return true };
+        let arg = tokens.get(1).copied().unwrap_or("");
+        match *command {
+            "xboard" => {},
+            "protover" => {
+                println!("feature done=0");
+                println!("feature myname=\"{}\" variants=\"normal\"", env!("CARGO_PKG_NAME"));
+                println!("feature ping=1 setboard=1 usermove=1 san=0 colors=0 analyze=0");
+                println!("feature sigint=0 sigterm=0 reuse=1 smp=1");
+                #[cfg(feature = "nnue")]
+                {
+                    println!("feature option=\"EvalFile -file \"");
+                    println!("feature option=\"UseNNUE -check 1\"");
+                }
+                println!("feature done=1");
+            },
+            "accepted" | "rejected" => {},
+            "new" => {
+                self.halt_search();
+                self.cache.clear();
+                self.gstate = std_chess_position(self.hash_ch.clone());
+                self.force = false;
+                self.engine_color = Color::Black;
+                self.max_depth = u8::MAX;
+                self.clock = None;
+            },
+            "force" => {
+                self.halt_search();
+                self.force = true;
+            },
+            "go" => {
+                self.halt_search();
+                self.force = false;
+                self.engine_color = self.gstate.active_player();
+                self.begin_thinking();
+            },
+            "?" => if let Some(search) = &self.search { search.stop(); },
+            "usermove" => self.handle_usermove(arg),
+            "setboard" => {
+                self.halt_search();
+                let fen = tokens[1..].join(" ");
+                match ChessGame::from_fen(&fen, self.hash_ch.clone()) {
+                    Ok(gstate) => self.gstate = gstate,
+                    Err(err) => println!("tellusererror Illegal position: {:?}", err),
+                }
+            },
+            "undo" => {
+                self.halt_search();
+                if !self.gstate.movelog.is_empty() { unmake_move(&mut self.gstate); }
+            },
+            "remove" => {
+                self.halt_search();
+                for _ in 0..2 {
+                    if !self.gstate.movelog.is_empty() { unmake_move(&mut self.gstate); }
+                }
+            },
+            "result" => {
+                self.halt_search();
+                self.force = true;
+            },
+            "level" => self.handle_level(&tokens[1..]),
+            "st" => self.fixed_think_time = arg.parse::<f64>().ok()
+                .map(|secs| Duration::from_secs_f64(secs.max(0.0))),
+            "sd" => self.max_depth = arg.parse().unwrap_or(u8::MAX),
+            "cores" => self.thread_count = arg.parse::<usize>().unwrap_or(1).max(1),
+            "time" => self.clock = arg.parse::<u64>().ok()
+                .map(|centis| Duration::from_millis(centis * 10)),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "ping" => println!("pong {}", arg),
+            #[cfg(feature = "nnue")]
+            "option" => {
+                self.handle_option(line.trim().strip_prefix("option").unwrap_or("").trim());
+            },
+            "quit" => return false,
+            "otim" | "hard" | "easy" | "random" | "computer" | "draw" | "name" | "rating" => {},
+            other => println!("Error (unknown command): {}", other)
+        }
+        return true;
+    }
+
+    fn handle_usermove(&mut self, token: &str) {
+        self.halt_search();
+        match parse_lan(&mut self.gstate, token) {
+            Ok(mov) => make_move(&mut self.gstate, mov),
+            Err(_) => {
+                println!("Illegal move: {}", token);
+                return;
+            }
+        }
+        if !self.force && self.gstate.active_player() == self.engine_color {
+            self.begin_thinking();
+        }
+    }
+
+    /// Parses `option NAME=VALUE` for the options announced in response to `protover`.
+    #[cfg(feature = "nnue")]
+    fn handle_option(&mut self, setting: &str) {
+        let Some((name, value)) = setting.split_once('=') else {
+            println!("Error (malformed option): {}", setting);
+            return;
+        };
+        match name {
+            "EvalFile" => {
+                self.halt_search();
+                match load_network(value) {
+                    Ok(()) => {
+                        refresh_accumulator(&mut self.gstate);
+                        // The cached scores were computed by the previous evaluator.
+                        self.cache.clear();
+                    },
+                    Err(err) => println!("Error (failed to load network {}): {:?}", value, err)
+                }
+            },
+            "UseNNUE" => {
+                self.halt_search();
+                if set_nnue_enabled(value == "1") { self.cache.clear(); }
+            },
+            other => println!("Error (unknown option): {}", other)
+        }
+    }
+
+    /// Parses `level MPS BASE INC` where `BASE` is given in minutes,
+    /// optionally with seconds (`m:ss`), and `INC` is given in seconds.
+    fn handle_level(&mut self, tokens: &[&str]) {
+        let [mps, base, inc] = tokens else {
+            println!("Error (malformed level): {}", tokens.join(" "));
+            return;
+        };
+        self.moves_per_session = mps.parse().unwrap_or(0);
+        self.clock = parse_base_time(base);
+        self.increment = inc.parse::<f64>().ok()
+            .map(|secs| Duration::from_secs_f64(secs.max(0.0)))
+            .unwrap_or_default();
+        self.fixed_think_time = None;
+    }
+
+    fn begin_thinking(&mut self) {
+        if matches!(status(&mut self.gstate), GameStatus::Complete(_)) { return; }
+        let time_manager = self.calc_time_manager();
+        let deadline = match &time_manager {
+            Some(time_manager) => time_manager.hard_deadline(),
+            None => Instant::now() + self.fixed_think_time.unwrap_or(DEFAULT_THINK_TIME)
+        };
+        let search_begin = Instant::now();
+        let post = self.post;
+        let mover = self.gstate.active_player();
+        let id = self.next_search_id;
+        self.next_search_id += 1;
+        self.search = Some(spawn_search(BackgroundSearchParams { id,
+            gstate: self.gstate.clone(), deadline, max_depth: self.max_depth,
+            cache: self.cache.clone(), thread_count: self.thread_count, time_manager,
+            events: self.events.clone() },
+            move |result| if post { report_iteration(result, mover, search_begin) }));
+    }
+
+    /// Constructs the [`TimeManager`] for a search timed by the engine's clock. Returns
+    /// `None` when the think time is fixed by `st`, or the clock is unknown.
+    fn calc_time_manager(&self) -> Option<TimeManager> {
+        if self.fixed_think_time.is_some() { return None; }
+        let remaining = self.clock?;
+        let moves_to_go = match self.moves_per_session {
+            0 => None,
+            mps => {
+                let ply = usize::from(self.gstate.initial_ply) + self.gstate.movelog.len();
+                let moves_made = u32::try_from(ply / 2).unwrap();
+                Some(mps - (moves_made % mps))
+            }
+        };
+        let clock = Clock { remaining, increment: self.increment, moves_to_go };
+        return Some(TimeManager::new(calc_time_limits(&clock)));
+    }
+
+    fn handle_search_complete(&mut self, id: u64, result: IterDeepSearchResult) {
+        if self.search.as_ref().map(|s| s.id) != Some(id) { return; }
+        self.search.take().unwrap().halt();
+        println!("move {}", format_lan(result.bestmove, self.gstate.active_player()));
+        make_move(&mut self.gstate, result.bestmove);
+        match status(&mut self.gstate) {
+            GameStatus::Complete(GameResult::Diff(Color::White)) => println!("1-0 {{White mates}}"),
+            GameStatus::Complete(GameResult::Diff(Color::Black)) => println!("0-1 {{Black mates}}"),
+            GameStatus::Complete(GameResult::Tie) => println!("1/2-1/2 {{Stalemate}}"),
+            GameStatus::Incomplete => {
+                if count_repetitions(&self.gstate) >= 3 {
+                    println!("1/2-1/2 {{Draw by repetition}}");
+                } else if self.gstate.halfmoveclock >= 100 {
+                    println!("1/2-1/2 {{Draw by fifty move rule}}");
+                }
+            }
+        }
+    }
+
+    /// Cancels the ongoing search (if any) without playing a move.
+    fn halt_search(&mut self) {
+        if let Some(search) = self.search.take() { search.halt(); }
+    }
+}
+
+/// Emits thinking output in the `post` format: `ply score time nodes pv`,
+/// where the score is given in centipawns and the time in centiseconds.
+/// A forced mate in `n` moves is scored `100000 + n`, or `-100000 - n` when mated.
+fn report_iteration(result: &IterDeepSearchResult, mover: Color, search_begin: Instant) {
+    let score = match result.mate_in {
+        Some(moves) if moves > 0 => 100000 + i32::from(moves),
+        Some(moves) => -100000 + i32::from(moves),
+        None => to_centipawns(result.score)
+    };
+    println!("{} {} {} {} {}", result.depth_achieved, score,
+        search_begin.elapsed().as_millis() / 10, result.node_count,
+        format_lan_line(&result.pv, mover));
+}
+
+/// Parses the base time of `level`, given in minutes and optionally seconds (`m:ss`).
+fn parse_base_time(base: &str) -> Option<Duration> {
+    let (minutes, seconds) = base.split_once(':').unwrap_or((base, "0"));
+    let seconds = minutes.parse::<u64>().ok()? * 60 + seconds.parse::<u64>().ok()?;
+    return Some(Duration::from_secs(seconds));
+}
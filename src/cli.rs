@@ -1,10 +1,13 @@
 use crate::gamestate::{ChessGame, LoggedMove};
-use crate::grid::Side;
 use crate::grid::StandardCoordinate;
 use crate::mov::AnyMove;
 use crate::movegen::dispatch::movegen_legal;
 use crate::movegen::types::GeneratedMove;
 use crate::piece::Piece;
+use crate::san::ParseSanErr;
+use crate::san::format_san;
+use crate::san::parse_san;
+use std::io::Write;
 
 pub fn get_unicode_symbol(piece: Piece) -> &'static str {
     use crate::piece::Color::*;
@@ -69,27 +72,37 @@ pub fn prompt_ok() {
     std::io::stdin().read_line(&mut input);
 }
 
+/// Lists the legal moves in Standard Algebraic Notation and reads the player's
+/// choice, given either as SAN or as the index of the move in the list.
 pub fn prompt_move(state: &mut ChessGame) -> AnyMove {
     let mut moves: Vec<GeneratedMove> = Vec::new();
     movegen_legal(state, &mut moves);
     for (i, genmove) in moves.iter().enumerate() {
-        print!("{}. ", i);
-        match genmove.mov {
-            AnyMove::Piece(pmov) => print!("{} -> {}", pmov.origin, pmov.destin),
-            AnyMove::Castle(direction) => {
-                print!("Castle ");
-                match direction {
-                    Side::Queenside => print!("Queenside"),
-                    Side::Kingside => print!("Kingside"),
-                }
-            }
-        }
-        print!("\n");
+        println!("{}. {}", i, format_san(state, genmove.mov));
     }
 
-    print!("Move #: ");
-    let selected_index = prompt_usize();
-    return moves[selected_index].mov;
+    loop {
+        print!("Move: ");
+        std::io::stdout().flush();
+        let mut input = String::new();
+        let read_count = std::io::stdin().read_line(&mut input).unwrap();
+        assert!(read_count > 0, "standard input closed while awaiting a move");
+        let input = input.trim();
+        if let Some(genmove) = input.parse::<usize>().ok().and_then(|i| moves.get(i)) {
+            return genmove.mov;
+        }
+        match parse_san(state, input) {
+            Ok(mov) => return mov,
+            Err(ParseSanErr::AmbiguousMove(candidates)) => {
+                let candidates: Vec<String> = candidates.into_iter()
+                    .map(|mov| format_san(state, mov))
+                    .collect();
+                println!("Ambiguous move, did you mean {}?", candidates.join(" or "));
+            },
+            Err(ParseSanErr::IllegalMove) => println!("Illegal move: {}", input),
+            Err(err) => println!("Malformed move: {} ({:?})", input, err),
+        }
+    }
 }
 
 pub fn prompt_usize() -> usize {
@@ -105,7 +118,7 @@ pub fn format_integer<T>(num: T) -> String where T: ToString {
     for (index, ch) in s.clone().char_indices().rev() {
         assert!(ch.is_ascii_digit());
         if (original_length - index) % 3 == 0 {
-            if index + 1 != original_length {
+            if (index + 1 != original_length) & (index != 0) {
                 s.insert(index, ',');
             }
         }
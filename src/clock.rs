@@ -0,0 +1,149 @@
+//! Time management for games played on a clock. Given the state of the clock, the
+//! think time for a move is bounded by two limits. Once the *soft limit* has passed,
+//! no further iteration of the search is begun, and once the *hard limit* has passed,
+//! the ongoing iteration is abandoned. Between the two, the [`TimeManager`] adapts to
+//! the search: it stops early when the best move is stable, thinks longer when the
+//! score drops (a fail-low), and never begins an iteration which it expects cannot
+//! finish before the hard limit, since an abandoned iteration is wasted time.
+
+use crate::search::IterDeepSearchResult;
+use crate::mov::AnyMove;
+use std::time::Duration;
+use std::time::Instant;
+
+// # Constants
+
+/// Reserved on every move for the latency between the engine and the controller.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+
+/// The number of moves the remaining time is expected to last when the controller
+/// does not say how many moves remain until the next time control.
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// The hard limit is at most this multiple of the soft limit...
+const HARD_LIMIT_FACTOR: u32 = 4;
+/// ...and, unless this is the last move before the time control, at most this
+/// fraction of the remaining time, in percent.
+const MAX_HARD_LIMIT_PERCENT: u32 = 50;
+
+/// Scales the soft limit, in percent, by the number of consecutive iterations which
+/// agreed upon the best move. A best move which keeps changing warrants more thought.
+const STABILITY_SCALE: [u32; 5] = [150, 110, 90, 70, 50];
+
+/// A drop in score of more than this many centipawns from one iteration to the next
+/// is considered a fail-low, which extends the soft limit by [`FAIL_LOW_EXTENSION`].
+const FAIL_LOW_MARGIN: i16 = 30;
+const FAIL_LOW_EXTENSION: u32 = 200;
+
+/// Bounds the ratio between the durations of successive iterations, which estimates
+/// the duration of the next iteration from the duration of the last.
+const MIN_GROWTH: f64 = 1.5;
+const MAX_GROWTH: f64 = 4.0;
+
+// # Time Limits
+
+/// The state of the clock of the player to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    pub remaining: Duration,
+    /// The time added to the clock after each move.
+    pub increment: Duration,
+    /// The number of moves until the next time control, if known. Otherwise the
+    /// remaining time must last the rest of the game (sudden death).
+    pub moves_to_go: Option<u32>
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeLimits {
+    pub soft: Duration,
+    pub hard: Duration
+}
+
+impl TimeLimits {
+    /// Limits which allow exactly the given think time, for instance when the
+    /// controller dictates a fixed time per move.
+    pub fn fixed(think_time: Duration) -> Self {
+        return Self { soft: think_time, hard: think_time };
+    }
+}
+
+/// Computes the limits of the think time for the next move. The remaining time is
+/// divided evenly among the moves to go, and most of the increment is spent as well,
+/// as it is regained after the move. Neither limit ever exceeds the remaining time.
+pub fn calc_time_limits(clock: &Clock) -> TimeLimits {
+    let moves_to_go = clock.moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+    let available = clock.remaining.saturating_sub(MOVE_OVERHEAD);
+    let soft = (available / moves_to_go + clock.increment * 3 / 4).min(available);
+    let max_hard = match moves_to_go {
+        1 => available,
+        _ => available * MAX_HARD_LIMIT_PERCENT / 100
+    };
+    let hard = (soft * HARD_LIMIT_FACTOR).min(max_hard).max(soft);
+    return TimeLimits { soft, hard };
+}
+
+// # `TimeManager`
+
+/// Decides, after each iteration of the search, whether to begin the next iteration.
+/// See the [module documentation](self).
+#[derive(Clone, Copy, Debug)]
+pub struct TimeManager {
+    pub limits: TimeLimits,
+    begin: Instant,
+    /// The time elapsed at the end of each of the two most recent iterations.
+    last_elapsed: Duration,
+    prev_elapsed: Duration,
+    prev_bestmove: Option<AnyMove>,
+    prev_score: Option<i16>,
+    /// The number of consecutive iterations which agreed upon the best move.
+    stability: usize
+}
+
+impl TimeManager {
+    /// Begins timing a search which starts now.
+    pub fn new(limits: TimeLimits) -> Self {
+        return Self { limits, begin: Instant::now(), last_elapsed: Duration::ZERO,
+            prev_elapsed: Duration::ZERO, prev_bestmove: None, prev_score: None, stability: 0 };
+    }
+
+    /// The instant after which the search must be abandoned, which should be
+    /// given to the search as its deadline.
+    pub fn hard_deadline(&self) -> Instant { self.begin + self.limits.hard }
+
+    /// Records the result of the iteration which just completed and determines if
+    /// the next iteration should begin.
+    pub fn should_deepen(&mut self, result: &IterDeepSearchResult) -> bool {
+        return self.should_deepen_at(self.begin.elapsed(), result);
+    }
+
+    /// Like [`Self::should_deepen`], but with the time elapsed since the search began
+    /// given explicitly instead of read from the system clock.
+    pub fn should_deepen_at(&mut self, elapsed: Duration, result: &IterDeepSearchResult) -> bool {
+        let iteration_time = elapsed.saturating_sub(self.last_elapsed);
+        let prev_iteration_time = self.last_elapsed.saturating_sub(self.prev_elapsed);
+        self.prev_elapsed = self.last_elapsed;
+        self.last_elapsed = elapsed;
+
+        if self.prev_bestmove == Some(result.bestmove) {
+            self.stability = (self.stability + 1).min(STABILITY_SCALE.len() - 1);
+        } else {
+            self.stability = 0;
+        }
+        let is_fail_low = self.prev_score
+            .is_some_and(|prev| i32::from(result.score) + i32::from(FAIL_LOW_MARGIN)
+                < i32::from(prev));
+        self.prev_bestmove = Some(result.bestmove);
+        self.prev_score = Some(result.score);
+
+        let mut soft = self.limits.soft * STABILITY_SCALE[self.stability] / 100;
+        if is_fail_low { soft = soft * FAIL_LOW_EXTENSION / 100; }
+        if elapsed >= soft.min(self.limits.hard) { return false; }
+
+        let growth = if prev_iteration_time.is_zero() { MAX_GROWTH } else {
+            (iteration_time.as_secs_f64() / prev_iteration_time.as_secs_f64())
+                .clamp(MIN_GROWTH, MAX_GROWTH)
+        };
+        let projected = elapsed + iteration_time.mul_f64(growth);
+        return projected < self.limits.hard;
+    }
+}
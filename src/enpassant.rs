@@ -1,4 +1,5 @@
 use crate::grid::File;
+use crate::grid::StandardCoordinate;
 use crate::movegen::pawn::reverse_pawn_attack;
 use crate::gamestate::LoggedMove;
 use crate::gamestate::ChessGame;
@@ -8,21 +9,27 @@ use crate::rmrel::relativize;
 /// double pawn jump. Meaning, the opponent double jumped AND
 /// the active player has a pawn in position to capture enpassant.
 pub fn is_enpassant_vuln(state: &ChessGame) -> Option<File> {
-    if let Some(last_entry) = state.movelog.last().copied() {
-        if let LoggedMove::Piece(pmove) = last_entry.lmove {
-            if pmove.is_pdj {
-                let target_rmrel = relativize(pmove.mgmove.destin,
-                    state.active_player());
-                
-                let mut bb = reverse_pawn_attack(target_rmrel + 8);
-                bb &= state.bbs.affilia_rel_bbs[state.active_player()];
-                bb &= state.bbs.pawn_rel_bb;
+    if let Some(pdj_destin) = locate_last_pdj(state) {
+        let target_rmrel = relativize(pdj_destin, state.active_player());
+        
+        let mut bb = reverse_pawn_attack(target_rmrel + 8);
+        bb &= state.bbs.affilia_rel_bbs[state.active_player()];
+        bb &= state.bbs.pawn_rel_bb;
 
-                if bb != 0 { 
-                    return Some(pmove.mgmove.destin.file()) 
-                }
-            }
+        if bb != 0 { 
+            return Some(pdj_destin.file()) 
         }
     }
     return None;
 }
+
+/// Locates the opponent's pawn if it double jumped on the previous ply.
+pub fn locate_last_pdj(state: &ChessGame) -> Option<StandardCoordinate> {
+    let Some(last_entry) = state.movelog.last().copied() else {
+        return state.initial_pdj;
+    };
+    if let LoggedMove::Piece(pmove) = last_entry.lmove {
+        if pmove.is_pdj { return Some(pmove.mgmove.destin); }
+    }
+    return None;
+}
@@ -1,92 +1,317 @@
 use crate::cache::Cache;
 use crate::cache::CacheValue;
+use crate::cache::ScoreBound;
+use crate::mov::AnyMove;
+use crate::mov::EncodedMove;
+use crate::mov::get_target_sq;
 use crate::{defer, early_ok};
 use crate::gamestate::ChessGame;
 use crate::makemove::{inspect_move, make_move};
 use crate::makemove::unmake_move;
-use crate::mat_eval::calc_matdiff;
+use crate::makemove::{make_null_move, unmake_null_move};
+use crate::gamestate::LoggedMove;
+use crate::king_safety::calc_king_safety;
+use crate::mobility::calc_mobility;
+#[cfg(feature = "nnue")]
+use crate::nnue::is_nnue_enabled;
+#[cfg(feature = "nnue")]
+use crate::nnue::nnue_eval;
+use crate::eval_params::EVAL_PARAMS;
+use crate::gamestate::Bitboards;
+use crate::params::EvalParams;
+use crate::pawn_eval::PawnCache;
+use crate::pawn_eval::calc_pawn_structure;
+use crate::piece::ColorTable;
+use crate::pos_eval::calc_positional_score;
+use crate::pos_eval::calc_tapered_eval;
 use crate::misc::{Max, pick, SegVec};
 use crate::movegen::dispatch::count_legal_moves;
 use crate::movegen::dispatch::movegen_legal;
 use crate::repetitions::count_repetitions;
 use crate::early_return;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::cmp::max;
 use std::time::Instant;
 use crate::grid::Side;
 use crate::movegen::types::GeneratedMove;
+use crate::movesort::movegen_captures_sorted;
+use crate::movesort::MoveHistory;
 use crate::movesort::movegen_legal_sorted;
+use crate::see::static_exchange_eval;
 use crate::snapshot::capture_snapshot;
 
 pub const MAX_SCORE: i16 = i16::MAX - 1;
 pub const MIN_SCORE: i16 = i16::MIN + 2;
 pub const BELOW_MIN_SCORE: i16 = i16::MIN + 1;
+pub const ABOVE_MAX_SCORE: i16 = i16::MAX;
+
+/// Converts a score into hundredths of a pawn, the unit expected by chess
+/// engine protocols. The heuristic score function already scores in centipawns.
+pub fn to_centipawns(score: i16) -> i32 {
+    return i32::from(score);
+}
+
+// # Mate Scores
+//
+// A checkmate `n` plies from the root of the search is scored `MIN_SCORE + n` from
+// the perspective of the mated player, and so `MAX_SCORE - n` from the perspective
+// of the mating player. Nearer mates are thereby preferred, and further mates
+// are resisted.
+
+/// The greatest distance from the root, in plies, at which a checkmate is
+/// distinguishable from any material score.
+pub const MAX_MATE_PLY: i16 = 1024;
+
+/// Determines if the given score was assigned by finding a checkmate, rather
+/// than by the heuristic score function.
+pub fn is_mate_score(score: i16) -> bool {
+    return (score >= MAX_SCORE - MAX_MATE_PLY) | (score <= MIN_SCORE + MAX_MATE_PLY);
+}
+
+/// Computes the number of moves until checkmate given a score from the perspective
+/// of the active-player. The number is positive if the active-player delivers mate
+/// and negative if the active-player is mated. If the score is not a mate score
+/// then `None` is returned.
+pub fn mate_in(score: i16) -> Option<i16> {
+    if !is_mate_score(score) { return None; }
+    if score > 0 { return Some((MAX_SCORE - score + 1) / 2); }
+    return Some(-1 * (score - MIN_SCORE) / 2);
+}
 
 // # Time Constrained Evaluation
 
-pub struct DeepEvalContext<'a, 'b, 'c, 'd> {
+pub struct DeepEvalContext<'a, 'b, 'c, 'd, 'e, 'f, 'g> {
     pub gstate: &'a mut ChessGame,
     /// The number of complete plys to play-out before applying 
     /// the heuristic score function to the position. When zero,
     /// the heuristic score function is applied immediately.
     pub lookahead: u8,
+    /// The number of plys played since the root of the search.
+    pub ply: u8,
     /// The buffer used to hold lookahead moves after they
     /// are generated but before they are evaluated. This
     /// buffer should be empty when `DeepEvalContext` is
     /// constructed by the caller.
     pub movebuf: SegVec<'b, GeneratedMove>,
     pub deadline: Instant,
-    /// The best score that the parent is assured of so-far.
-    /// If a child/opponent move is encountered with a score 
-    /// better than `cutoff`, this branch is pruned (not explored),
-    /// as the opponent will surely take this branch given the
-    /// opportunity, and so it is not interesting to us.
-    pub cutoff: i16,
-    pub cache: &'c mut Cache,
-    pub node_count: &'d mut u64
+    /// The best score that the active-player is assured of so-far elsewhere
+    /// in the tree. Any line scoring no better than `alpha` is uninteresting,
+    /// as the active-player will never enter it.
+    pub alpha: i16,
+    /// The best score that the opponent will allow the active-player to achieve,
+    /// as the opponent is assured of a better line elsewhere in the tree. Once a
+    /// move scoring at least `beta` is found, the remaining moves are pruned,
+    /// as the opponent will never give us the opportunity to make them.
+    pub beta: i16,
+    pub cache: &'c Cache,
+    pub node_count: &'d mut u64,
+    /// Raised by another thread to cancel the search before the deadline.
+    /// Cancellation is reported identically to an elapsed deadline.
+    pub stop: &'e AtomicBool,
+    pub history: &'f mut MoveHistory,
+    pub pawn_cache: &'g mut PawnCache
 }
 
-pub enum DeepEvalException { DeadlineElapsed, Cut }
+pub enum DeepEvalException { DeadlineElapsed }
 
 /// Computes the best score the active-player is assured of, assuming perfect play 
 /// by the opponent. When the deadline elapses, the search is cancelled and
 /// `Err(DeadlineElapsed)` is returned.
+///
+/// The score is exact only when it lies strictly between `alpha` and `beta`.
+/// Otherwise the search fails soft: a score at most `alpha` is an upper bound on
+/// the true score, and a score at least `beta` is a lower bound.
 pub fn deep_eval(mut ctx: DeepEvalContext) -> Result<i16, DeepEvalException> {
     *ctx.node_count += 1;
     if Instant::now() > ctx.deadline { return Err(DeepEvalException::DeadlineElapsed); }
-    if ctx.lookahead == 0 { return Ok(shallow_eval(ctx.gstate)); }
-    movegen_legal_sorted(ctx.gstate, &mut ctx.movebuf, ctx.cache);
-    early_ok! { leaf_eval(ctx.gstate, ctx.movebuf.is_empty()) };
-    early_ok! { ctx.cache.lookup_score_atleast(ctx.gstate, ctx.lookahead) };
+    if ctx.stop.load(Ordering::Relaxed) { return Err(DeepEvalException::DeadlineElapsed); }
+    if ctx.lookahead == 0 { return quiesce(ctx); }
+    movegen_legal_sorted(ctx.gstate, &mut ctx.movebuf, ctx.cache, ctx.history, ctx.ply);
+    early_ok! { leaf_eval(ctx.gstate, ctx.movebuf.is_empty(), ctx.ply) };
+    early_ok! { ctx.cache.lookup_score(ctx.gstate, ctx.lookahead, ctx.ply, ctx.alpha,
+        ctx.beta) };
 
+    if is_null_move_viable(&mut ctx) {
+        make_null_move(ctx.gstate);
+        let (lookahead, beta) = (ctx.lookahead - 1 - NULL_MOVE_REDUCTION, ctx.beta);
+        let result = eval_child(&mut ctx, lookahead, beta - 1, beta);
+        unmake_null_move(ctx.gstate);
+        let score = result?;
+        // The opponent cannot actually be mated by passing, so a mate score is
+        // not proven, only that the position is at least as good as `beta`.
+        if score >= ctx.beta { return Ok(pick(is_mate_score(score), ctx.beta, score)); }
+    }
+
+    let is_check = ctx.gstate.bbs.is_check();
     let mut best: Max<GeneratedMove, i16> = Max::new(BELOW_MIN_SCORE);
+    let mut index: usize = 0;
     while let Some(genmove) = ctx.movebuf.pop() {
-        let result = inspect_move(ctx.gstate, genmove.mov, |gstate| {
-            deep_eval(DeepEvalContext { gstate, lookahead: ctx.lookahead - 1,
-                movebuf: ctx.movebuf.extend(), deadline: ctx.deadline, cutoff: best.value(),
-                cache: ctx.cache, node_count: ctx.node_count })
-        });
-        match result {
-            Err(DeepEvalException::DeadlineElapsed) =>
-                return Err(DeepEvalException::DeadlineElapsed),
-            Err(DeepEvalException::Cut) => {},
-            Ok(score) => {
-                // The larger `score` is the better this position is for C.
-                // The larger `score * -1` is the better this position is for N.
-                // Recall `ctx.cutoff` is the highest score that P is assured of.
-                // Then `ctx.cutoff * -1` is the highest score that P will allow N to achieve. 
-                // So if this position is better for N than the best score for N that the P will allow,
-                // we cut it, as P will never give us an opportunity to make this move (they have
-                // a better choice already).
-                if score * -1 >= ctx.cutoff * -1 { return Err(DeepEvalException::Cut); }
-                best.push(genmove, -1 * score);
-            },
+        let alpha = max(ctx.alpha, best.value());
+        let is_quiet = is_quiet_move(ctx.gstate, genmove.mov);
+        make_move(ctx.gstate, genmove.mov);
+        let is_reducible = is_quiet & !is_check & !ctx.gstate.bbs.is_check();
+        let reduction = pick(is_reducible, calc_reduction(ctx.lookahead, index), 0);
+        let result = eval_move(&mut ctx, index == 0, reduction, alpha);
+        unmake_move(ctx.gstate);
+        let score = result?;
+        index += 1;
+        // A score no better than the best so-far is only an upper bound,
+        // and so this move is not known to be as good as the best move.
+        if score > best.value() { best.push(genmove, score); }
+        if best.value() >= ctx.beta {
+            if is_quiet {
+                ctx.history.record_cutoff(ctx.gstate, genmove.mov, ctx.ply, ctx.lookahead);
+            }
+            break;
         }
     }
-    let bestmov_id = best.item().unwrap().gen_id;
-    ctx.cache.update(ctx.gstate, ctx.lookahead, CacheValue {
-        bestmov_id, score: best.value() });
+    let bound = match best.value() {
+        score if score >= ctx.beta => ScoreBound::Lower,
+        score if score <= ctx.alpha => ScoreBound::Upper,
+        _ => ScoreBound::Exact
+    };
+    let bestmov = EncodedMove::encode(best.item().unwrap().mov);
+    ctx.cache.update(ctx.gstate, ctx.lookahead, ctx.ply, bound, CacheValue {
+        bestmov, score: best.value() });
     return Ok(best.value());
 }
 
+/// Evaluates the position following a move, or null move, with the given lookahead
+/// and window, from the perspective of the player who moved.
+fn eval_child(ctx: &mut DeepEvalContext, lookahead: u8, alpha: i16, beta: i16)
+-> Result<i16, DeepEvalException>
+{
+    let score = deep_eval(DeepEvalContext { gstate: ctx.gstate, lookahead,
+        ply: ctx.ply.saturating_add(1), movebuf: ctx.movebuf.extend(),
+        deadline: ctx.deadline, alpha: -1 * beta, beta: -1 * alpha,
+        cache: ctx.cache, node_count: ctx.node_count, stop: ctx.stop,
+        history: ctx.history, pawn_cache: ctx.pawn_cache })?;
+    return Ok(-1 * score);
+}
+
+// # Principal Variation Search
+//
+// Since the moves are ordered best-first, the first move is expected to be the best.
+// Only the first move is evaluated with the full window. Each later move is evaluated
+// with a null window, which can only prove that the move is no better than `alpha`,
+// but does so much more cheaply. Only when this proof fails is the move evaluated
+// again with the full window.
+//
+// Late quiet moves are even less likely to be best, and so the null-window evaluation
+// of such a move is additionally reduced in depth (late move reduction). Once again,
+// if the move proves better than `alpha`, it is evaluated again without reduction.
+
+/// The index in the move ordering of the first move which may be reduced.
+const LMR_MIN_INDEX: usize = 3;
+
+/// The least lookahead at which moves may be reduced.
+const LMR_MIN_LOOKAHEAD: u8 = 3;
+
+fn calc_reduction(lookahead: u8, index: usize) -> u8 {
+    if (lookahead < LMR_MIN_LOOKAHEAD) | (index < LMR_MIN_INDEX) { return 0; }
+    return pick(index >= 2 * LMR_MIN_INDEX, 2, 1).min(lookahead - 2);
+}
+
+/// Evaluates the position following the move just made, from the perspective of the
+/// player who made it. The score is exact, or a bound, as described by [`deep_eval`].
+fn eval_move(ctx: &mut DeepEvalContext, is_first: bool, reduction: u8, alpha: i16)
+-> Result<i16, DeepEvalException>
+{
+    let lookahead = ctx.lookahead - 1;
+    if is_first { return eval_child(ctx, lookahead, alpha, ctx.beta); }
+    let mut score = eval_child(ctx, lookahead - reduction, alpha, alpha + 1)?;
+    if (reduction > 0) & (score > alpha) {
+        score = eval_child(ctx, lookahead, alpha, alpha + 1)?;
+    }
+    if (score > alpha) & (score < ctx.beta) {
+        score = eval_child(ctx, lookahead, alpha, ctx.beta)?;
+    }
+    return Ok(score);
+}
+
+/// Determines if the given move neither captures nor promotes.
+fn is_quiet_move(gstate: &mut ChessGame, mov: AnyMove) -> bool {
+    let AnyMove::Piece(pmove) = mov else { return true };
+    let target = get_target_sq(pmove, gstate);
+    return gstate.p_lut.get(target).is_none() & pmove.promote.is_none();
+}
+
+// # Null-Move Pruning
+//
+// If the active-player could pass the turn and still score at least `beta`
+// in a reduced-depth search, then some real move almost certainly scores at least
+// `beta` too, and the node is pruned without searching any real move. This assumption
+// fails in zugzwang, where every move worsens the position, and so the null move
+// is not considered in the positions where zugzwang is likely.
+
+/// The number of plys, in addition to the null move itself, by which the search
+/// following a null move is reduced.
+const NULL_MOVE_REDUCTION: u8 = 2;
+
+fn is_null_move_viable(ctx: &mut DeepEvalContext) -> bool {
+    if ctx.lookahead <= NULL_MOVE_REDUCTION { return false; }
+    if is_mate_score(ctx.beta) { return false; }
+    // Passing twice in a row only returns to the same position.
+    let is_after_null = ctx.gstate.movelog.last()
+        .is_some_and(|entry| entry.lmove == LoggedMove::Null);
+    if is_after_null { return false; }
+    if ctx.gstate.bbs.is_check() { return false; }
+    if !ctx.gstate.bbs.has_non_pawn_material(ctx.gstate.active_player()) { return false; }
+    return heuristic_eval(ctx.gstate, ctx.pawn_cache) >= ctx.beta;
+}
+
+// # Quiescence Search
+
+/// Extends the search beyond the lookahead horizon by playing out captures and
+/// promotions until the position is quiet, so that the heuristic score function
+/// is never applied midway through an exchange. When not in check, the active-player
+/// may decline every capture ("stand pat"), so the heuristic score of the current
+/// position is a lower bound on its score. When in check, every evasion is considered.
+fn quiesce(mut ctx: DeepEvalContext) -> Result<i16, DeepEvalException> {
+    let is_check = ctx.gstate.bbs.is_check();
+    if is_check {
+        movegen_legal_sorted(ctx.gstate, &mut ctx.movebuf, ctx.cache, ctx.history, ctx.ply);
+    }
+    else { movegen_captures_sorted(ctx.gstate, &mut ctx.movebuf); }
+    // Stalemate is not detected here, as proving it costs a full legal move generation
+    // at every quiet node. When in check, every evasion was generated, so none means mate.
+    let cant_move = is_check && ctx.movebuf.is_empty();
+    early_ok! { leaf_eval(ctx.gstate, cant_move, ctx.ply) };
+
+    let mut best: i16 = BELOW_MIN_SCORE;
+    if !is_check {
+        best = heuristic_eval(ctx.gstate, ctx.pawn_cache);
+        if best >= ctx.beta { return Ok(best); }
+    }
+
+    while let Some(genmove) = ctx.movebuf.pop() {
+        if !is_check && is_losing_capture(ctx.gstate, genmove.mov) { continue; }
+        let alpha = max(ctx.alpha, best);
+        let score = -1 * inspect_move(ctx.gstate, genmove.mov, |gstate| {
+            *ctx.node_count += 1;
+            if Instant::now() > ctx.deadline { return Err(DeepEvalException::DeadlineElapsed); }
+            if ctx.stop.load(Ordering::Relaxed) { return Err(DeepEvalException::DeadlineElapsed); }
+            quiesce(DeepEvalContext { gstate, lookahead: 0, ply: ctx.ply.saturating_add(1),
+                movebuf: ctx.movebuf.extend(),
+                deadline: ctx.deadline, alpha: -1 * ctx.beta, beta: -1 * alpha,
+                cache: ctx.cache, node_count: ctx.node_count, stop: ctx.stop,
+                history: ctx.history, pawn_cache: ctx.pawn_cache })
+        })?;
+        best = max(best, score);
+        if best >= ctx.beta { return Ok(best); }
+    }
+    return Ok(best);
+}
+
+/// Determines if the given capture loses material once the exchange which follows
+/// is resolved. Such captures are not considered by quiescence search, since the
+/// active-player could expect to stand pat instead.
+fn is_losing_capture(gstate: &mut ChessGame, mov: AnyMove) -> bool {
+    let AnyMove::Piece(pmove) = mov else { return false };
+    return static_exchange_eval(gstate, pmove) < 0;
+}
+
 // # Shallow Evaluation
 
 /// Evaluates the given position with no lookahead and no deadline. 
@@ -98,15 +323,44 @@ pub fn deep_eval(mut ctx: DeepEvalContext) -> Result<i16, DeepEvalException> {
 /// If there is less time remaining on our clock than the amount of time
 /// required to execute this procedure, the game is necessarily lost.
 /// The short runtime of `shallow_eval` is at the expense of accuracy.
-pub fn shallow_eval(gstate: &mut ChessGame) -> i16 {
+/// The `ply` is the distance from the root of the search, see [`mate_in`].
+pub fn shallow_eval(gstate: &mut ChessGame, ply: u8, pawn_cache: &mut PawnCache) -> i16 {
     let cant_move = count_legal_moves(gstate) == 0;
-    early_return! { leaf_eval(gstate, cant_move) };
-    return calc_matdiff(&gstate.bbs);
+    early_return! { leaf_eval(gstate, cant_move, ply) };
+    return heuristic_eval(gstate, pawn_cache);
+}
+
+/// Scores a position which is neither checkmate nor drawn from the perspective
+/// of the active-player, in centipawns. With the `nnue` feature, the position is
+/// scored by the network instead, if one is loaded and enabled.
+fn heuristic_eval(gstate: &ChessGame, pawn_cache: &mut PawnCache) -> i16 {
+    #[cfg(feature = "nnue")]
+    if is_nnue_enabled() {
+        if let Some(score) = nnue_eval(gstate) { return score; }
+    }
+    let mut score = gstate.pos_score;
+    score += pawn_cache.lookup(gstate);
+    score += calc_king_safety(&gstate.bbs, &gstate.has_castled, &EVAL_PARAMS);
+    score += calc_mobility(&gstate.bbs, &EVAL_PARAMS);
+    return calc_tapered_eval(&gstate.bbs, score);
+}
+
+/// Computes the same score as the heuristic score function, but entirely from scratch
+/// and with the given parameters, rather than with [`EVAL_PARAMS`]. This is used for
+/// tuning, see [`crate::tune`].
+pub fn calc_static_eval(board: &Bitboards, has_castled: &ColorTable<bool>, params: &EvalParams)
+-> i16
+{
+    let mut score = calc_positional_score(board, params);
+    score += calc_pawn_structure(board, params);
+    score += calc_king_safety(board, has_castled, params);
+    score += calc_mobility(board, params);
+    return calc_tapered_eval(board, score);
 }
 
-fn leaf_eval(gstate: &mut ChessGame, cant_move: bool) -> Option<i16> {
+fn leaf_eval(gstate: &mut ChessGame, cant_move: bool, ply: u8) -> Option<i16> {
     if cant_move { // TODO: This branch instruction can be removed
-        return pick(gstate.bbs.is_check(), Some(MIN_SCORE), Some(0));
+        return pick(gstate.bbs.is_check(), Some(MIN_SCORE + i16::from(ply)), Some(0));
     }
     let by_repetition = count_repetitions(gstate) >= 3;
     let by_50moverule = gstate.halfmoveclock >= 100;
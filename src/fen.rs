@@ -0,0 +1,266 @@
+//! Forsyth–Edwards Notation, the standard single-line description of a chess position.
+
+use crate::cache::HashChars;
+use crate::coordinates::StandardCS;
+use crate::crights::CastlingRights;
+use crate::enpassant::is_enpassant_vuln;
+use crate::gamestate::ChessGame;
+use crate::gamestate::locate_king_stdc;
+use crate::grid::File;
+use crate::grid::ParseStandardCoordinateError;
+use crate::grid::Rank;
+use crate::grid::Side;
+use crate::grid::StandardCoordinate;
+use crate::makemove::fill_tile;
+use crate::makemove::swap_active;
+use crate::piece::Color;
+use crate::piece::Piece;
+use crate::piece::Species;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// The standard starting position.
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug)]
+pub enum ParseFenErr {
+    /// There must be at least four fields (placement, active color, castling,
+    /// enpassant) and at most six (halfmove clock, fullmove number).
+    BadFieldCount,
+    BadRankCount,
+    /// The rank describes more or fewer than eight tiles.
+    BadRankLength(Rank),
+    MalformedPiece(char),
+    BadKingCount(Color),
+    PawnOnBackRank,
+    /// The player who just moved has left their king in check.
+    InactiveKingInCheck,
+    MalformedActiveColor,
+    MalformedCastlingRights,
+    /// The castling rights claim a king or rook is home when it is not.
+    InconsistentCastlingRights,
+    MalformedEnpassant(ParseStandardCoordinateError),
+    /// The enpassant square is not directly behind a pawn which could
+    /// have just double jumped.
+    InconsistentEnpassant,
+    MalformedHalfmoveClock(std::num::ParseIntError),
+    MalformedFullmoveNumber(std::num::ParseIntError),
+    /// The fullmove number is too large for the ply count to be represented.
+    BadFullmoveNumber(u16),
+}
+
+impl ChessGame {
+    /// Constructs the position described by the given FEN string.
+    pub fn from_fen(fen: &str, hash_ch: HashChars) -> Result<Self, ParseFenErr> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if (fields.len() < 4) | (fields.len() > 6) { return Err(ParseFenErr::BadFieldCount); }
+
+        let mut state = ChessGame::new(hash_ch);
+        parse_placement(&mut state, fields[0])?;
+
+        let active_player = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(ParseFenErr::MalformedActiveColor)
+        };
+        if active_player == Color::Black { swap_active(&mut state); }
+        if is_attacked_inactive_king(&state) { return Err(ParseFenErr::InactiveKingInCheck); }
+
+        let crights = parse_crights(fields[2])?;
+        if !are_crights_consistent(&state, crights) {
+            return Err(ParseFenErr::InconsistentCastlingRights);
+        }
+        state.hash.toggle_crights(state.crights);
+        state.crights = crights;
+        state.hash.toggle_crights(state.crights);
+
+        state.initial_pdj = parse_enpassant(&state, fields[3])?;
+        state.hash.toggle_ep_vuln(is_enpassant_vuln(&state));
+
+        if let Some(field) = fields.get(4) {
+            state.halfmoveclock = field.parse()
+                .map_err(ParseFenErr::MalformedHalfmoveClock)?;
+        }
+        if let Some(field) = fields.get(5) {
+            let fullmove: u16 = field.parse()
+                .map_err(ParseFenErr::MalformedFullmoveNumber)?;
+            state.initial_ply = fullmove.saturating_sub(1).checked_mul(2)
+                .and_then(|ply| ply.checked_add(u16::from(active_player.index())))
+                .ok_or(ParseFenErr::BadFullmoveNumber(fullmove))?;
+        } else {
+            state.initial_ply = u16::from(active_player.index());
+        }
+
+        return Ok(state);
+    }
+
+    /// Describes the current position in FEN. The enpassant square is only written
+    /// when an enpassant capture is actually available to the active player.
+    pub fn to_fen(&self) -> String {
+        let mut fen = String::new();
+        for rank_i in (0..8u8).rev() {
+            let mut empty_count: u8 = 0;
+            for file_i in 0..8u8 {
+                let pos = StandardCoordinate::new(Rank::from_index(rank_i),
+                    File::from_index(file_i));
+                match self.p_lut.get(pos) {
+                    Some(piece) => {
+                        if empty_count > 0 { write!(fen, "{}", empty_count).unwrap(); }
+                        empty_count = 0;
+                        fen.push(get_piece_letter(piece));
+                    },
+                    None => empty_count += 1,
+                }
+            }
+            if empty_count > 0 { write!(fen, "{}", empty_count).unwrap(); }
+            if rank_i > 0 { fen.push('/'); }
+        }
+
+        fen.push_str(match self.active_player() {
+            Color::White => " w ",
+            Color::Black => " b ",
+        });
+
+        let mut any_crights = false;
+        for (side, color, letter) in CRIGHTS_LETTERS {
+            if self.crights.get(side, color) {
+                fen.push(letter);
+                any_crights = true;
+            }
+        }
+        if !any_crights { fen.push('-'); }
+
+        match is_enpassant_vuln(self) {
+            Some(file) => {
+                let rank = Rank::relative_to(self.active_player(), 5);
+                let pos = StandardCoordinate::new(rank, file);
+                write!(fen, " {}", pos.to_string().to_ascii_lowercase()).unwrap();
+            },
+            None => fen.push_str(" -"),
+        }
+
+        let ply = usize::from(self.initial_ply) + self.movelog.len();
+        write!(fen, " {} {}", self.halfmoveclock, ply / 2 + 1).unwrap();
+        return fen;
+    }
+}
+
+const CRIGHTS_LETTERS: [(Side, Color, char); 4] = [
+    (Side::Kingside, Color::White, 'K'),
+    (Side::Queenside, Color::White, 'Q'),
+    (Side::Kingside, Color::Black, 'k'),
+    (Side::Queenside, Color::Black, 'q'),
+];
+
+fn parse_placement(state: &mut ChessGame, field: &str) -> Result<(), ParseFenErr> {
+    let ranks: Vec<&str> = field.split('/').collect();
+    if ranks.len() != 8 { return Err(ParseFenErr::BadRankCount); }
+    for (i, rank_str) in ranks.iter().enumerate() {
+        let rank = Rank::from_index(7 - i as u8);
+        let mut file_i: u8 = 0;
+        for ch in rank_str.chars() {
+            if let Some(skip) = ch.to_digit(10) {
+                file_i += skip as u8;
+                if file_i > 8 { return Err(ParseFenErr::BadRankLength(rank)); }
+                continue;
+            }
+            let piece = parse_piece_letter(ch).ok_or(ParseFenErr::MalformedPiece(ch))?;
+            if file_i >= 8 { return Err(ParseFenErr::BadRankLength(rank)); }
+            let is_back_rank = (rank.index() == 0) | (rank.index() == 7);
+            if is_back_rank & (piece.species() == Species::Pawn) {
+                return Err(ParseFenErr::PawnOnBackRank);
+            }
+            fill_tile(state, StandardCoordinate::new(rank, File::from_index(file_i)), piece);
+            file_i += 1;
+        }
+        if file_i != 8 { return Err(ParseFenErr::BadRankLength(rank)); }
+    }
+    for color in [Color::White, Color::Black] {
+        let king_count = state.bbs.class::<StandardCS>(color, Species::King).count();
+        if king_count != 1 { return Err(ParseFenErr::BadKingCount(color)); }
+    }
+    return Ok(());
+}
+
+fn is_attacked_inactive_king(state: &ChessGame) -> bool {
+    let mut probe = state.clone();
+    swap_active(&mut probe);
+    return probe.bbs.is_check();
+}
+
+fn parse_crights(field: &str) -> Result<CastlingRights, ParseFenErr> {
+    let mut crights = CastlingRights::NONE;
+    if field == "-" { return Ok(crights); }
+    for ch in field.chars() {
+        let (side, color, _) = CRIGHTS_LETTERS.into_iter()
+            .find(|(_, _, letter)| *letter == ch)
+            .ok_or(ParseFenErr::MalformedCastlingRights)?;
+        crights.set(side, color, true);
+    }
+    return Ok(crights);
+}
+
+fn are_crights_consistent(state: &ChessGame, crights: CastlingRights) -> bool {
+    const ROOK_FILE_LUT: [File; 2] = [File::A, File::H];
+    let mut consistent = true;
+    for (side, color, _) in CRIGHTS_LETTERS {
+        if !crights.get(side, color) { continue; }
+        let base_rank = Rank::base_rank(color);
+        let king_home = StandardCoordinate::new(base_rank, File::E);
+        let rook_home = StandardCoordinate::new(base_rank,
+            ROOK_FILE_LUT[usize::from(side.index())]);
+        consistent &= locate_king_stdc(&state.bbs, color) == king_home;
+        consistent &= state.p_lut.get(rook_home) == Some(Piece::new(color, Species::Rook));
+    }
+    return consistent;
+}
+
+/// Resolves the FEN enpassant square to the location of the pawn which double jumped.
+fn parse_enpassant(state: &ChessGame, field: &str)
+-> Result<Option<StandardCoordinate>, ParseFenErr>
+{
+    if field == "-" { return Ok(None); }
+    let ep_square = StandardCoordinate::from_str(&field.to_ascii_uppercase())
+        .map_err(ParseFenErr::MalformedEnpassant)?;
+    let jumper = state.active_player().oppo();
+    if ep_square.rank() != Rank::relative_to(jumper, 2) {
+        return Err(ParseFenErr::InconsistentEnpassant);
+    }
+    let pdj_destin = StandardCoordinate::new(Rank::pdj_rank(jumper), ep_square.file());
+    let pdj_origin = StandardCoordinate::new(Rank::pawn_rank(jumper), ep_square.file());
+    let mut consistent = true;
+    consistent &= state.p_lut.get(pdj_destin) == Some(Piece::new(jumper, Species::Pawn));
+    consistent &= state.p_lut.get(ep_square).is_none();
+    consistent &= state.p_lut.get(pdj_origin).is_none();
+    if !consistent { return Err(ParseFenErr::InconsistentEnpassant); }
+    return Ok(Some(pdj_destin));
+}
+
+fn parse_piece_letter(ch: char) -> Option<Piece> {
+    let color = if ch.is_ascii_uppercase() { Color::White } else { Color::Black };
+    let species = match ch.to_ascii_lowercase() {
+        'p' => Species::Pawn,
+        'n' => Species::Knight,
+        'b' => Species::Bishop,
+        'r' => Species::Rook,
+        'q' => Species::Queen,
+        'k' => Species::King,
+        _ => return None
+    };
+    return Some(Piece::new(color, species));
+}
+
+fn get_piece_letter(piece: Piece) -> char {
+    let letter = match piece.species() {
+        Species::Pawn => 'p',
+        Species::Knight => 'n',
+        Species::Bishop => 'b',
+        Species::Rook => 'r',
+        Species::Queen => 'q',
+        Species::King => 'k',
+    };
+    return match piece.color() {
+        Color::White => letter.to_ascii_uppercase(),
+        Color::Black => letter,
+    };
+}
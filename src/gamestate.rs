@@ -19,6 +19,9 @@ use crate::piece::Piece;
 use crate::piece::PieceGrid;
 use crate::piece::Species;
 use crate::piece::SpeciesTable;
+use crate::pos_eval::PositionalScore;
+#[cfg(feature = "nnue")]
+use crate::nnue::Accumulator;
 
 // # `ChessGame`
 
@@ -30,7 +33,17 @@ pub struct ChessGame {
     pub crights: CastlingRights,
     pub halfmoveclock: u16,
     pub hash: IncrementalHash,
-    pub has_castled: ColorTable<bool>
+    pub pos_score: PositionalScore,
+    #[cfg(feature = "nnue")]
+    pub accumulator: Accumulator,
+    pub has_castled: ColorTable<bool>,
+    /// The number of plys played before the first entry in `movelog`.
+    /// This is nonzero only for positions which were set up mid-game, e.g. from FEN.
+    pub initial_ply: u16,
+    /// The destination of the pawn double jump (if any) made on the ply immediately
+    /// preceding the first entry in `movelog`. Positions set up mid-game have no
+    /// history from which an enpassant opportunity can otherwise be derived.
+    pub initial_pdj: Option<StandardCoordinate>
 }
 
 impl ChessGame {
@@ -47,7 +60,10 @@ impl ChessGame {
         let mut hash = IncrementalHash::new(hash_ch);
         hash.toggle_crights(crights);
         return Self { bbs, p_lut, movelog, crights, halfmoveclock,
-            hash, has_castled: ColorTable::default() };
+            hash, pos_score: PositionalScore::default(),
+            #[cfg(feature = "nnue")]
+            accumulator: Accumulator::new(),
+            has_castled: ColorTable::default(), initial_ply: 0, initial_pdj: None };
     }
 }
 
@@ -63,7 +79,9 @@ pub struct MovelogEntry {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LoggedMove {
     Castle(Side),
-    Piece(LoggedPieceMove)
+    Piece(LoggedPieceMove),
+    /// The active-player passed the turn, see [`crate::makemove::make_null_move`].
+    Null
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -114,6 +132,15 @@ impl Bitboards {
         return bb;
     }
 
+    /// Determines if the given player has any pieces besides pawns and the king.
+    /// Positions without such pieces are those most prone to zugzwang.
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        let mut bb: Bitboard<StandardCS> = self.affilia_bbs[color].get();
+        bb &= !self.species_bbs[Species::Pawn].get::<StandardCS>();
+        bb &= !self.species_bbs[Species::King].get::<StandardCS>();
+        return bb.is_not_empty();
+    }
+
     /// Determines if the active-player's king is in check.
     pub fn is_check(&self) -> bool { 
         is_attacked(&self, locate_king_stdc(&self, self.active_player))
@@ -148,11 +175,13 @@ pub fn locate_king_stdc(board: &Bitboards, color: Color) -> StandardCoordinate {
 
 // # Status
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameStatus {
     Complete(GameResult),
     Incomplete
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum GameResult {
     Diff(/* victor */ Color),
     Tie
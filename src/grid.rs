@@ -46,6 +46,12 @@ impl Display for Rank {
     }
 }
 
+impl Debug for Rank {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.index() + 1)
+    }
+}
+
 #[derive(Debug)]
 pub enum ParseRankErr {
     ParseIntErr(std::num::ParseIntError),
@@ -0,0 +1,156 @@
+//! King safety evaluation penalizes a king whose pawn cover is missing or under
+//! attack by advancing enemy pawns, which stands on or beside an open file, or
+//! whose surroundings ("king zone") are targeted by several enemy pieces at once.
+//! These dangers matter most while the opponent still has the material to mount an
+//! attack, and so the terms are weighted almost entirely toward the middlegame.
+
+use crate::bitboard::Bitboard;
+use crate::bitboard::MDBitboard;
+use crate::bitboard::RawBitboard;
+use crate::coordinates::AntidiagonalMajorCS;
+use crate::coordinates::CoordinateSystem;
+use crate::coordinates::FileMajorCS;
+use crate::coordinates::ProdiagonalMajorCS;
+use crate::coordinates::RankMajorCS;
+use crate::coordinates::StandardCS;
+use crate::gamestate::Bitboards;
+use crate::gamestate::locate_king_stdc;
+use crate::grid::StandardCoordinate;
+use crate::laneutils::lanescan;
+use crate::movegen::king::king_attack;
+use crate::movegen::knight::knight_attack;
+use crate::params::EvalParams;
+use crate::pawn_eval::file_mask;
+use crate::pawn_eval::forward_ranks_mask;
+use crate::piece::Color;
+use crate::piece::ColorTable;
+use crate::piece::Species;
+use crate::pos_eval::PositionalScore;
+
+// # Constants
+
+/// The furthest pawn considered part of the shield or the storm, see
+/// [`EvalParams::shield`] and [`EvalParams::storm`].
+const MAX_PAWN_DISTANCE: usize = 3;
+
+/// A lone attacker rarely amounts to a real threat, so the king zone is considered
+/// to be under attack only when at least this many pieces participate.
+const MIN_ATTACKERS: u8 = 2;
+
+/// The penalty grows quadratically with the number of attack units, since coordinated
+/// attacks are far more dangerous than the sum of their parts, until this limit.
+const MAX_ATTACK_PENALTY: i16 = 500;
+
+// # Evaluation
+
+/// Scores the safety of the king of the given player, from that player's perspective.
+fn eval_king_safety(board: &Bitboards, has_castled: bool, color: Color,
+    params: &EvalParams) -> PositionalScore
+{
+    let king = locate_king_stdc(board, color);
+    let mut score = PositionalScore::default();
+    score += eval_pawn_cover(board, king, color, params);
+    if has_castled { score += params.castled; }
+    score.mg -= calc_attack_penalty(board, king, color, params);
+    return score;
+}
+
+/// Scores the pawn shield, pawn storm and open files on the king's file and the
+/// files adjacent to it.
+fn eval_pawn_cover(board: &Bitboards, king: StandardCoordinate, color: Color,
+    params: &EvalParams) -> PositionalScore
+{
+    let ours = board.class::<StandardCS>(color, Species::Pawn).raw();
+    let theirs = board.class::<StandardCS>(color.oppo(), Species::Pawn).raw();
+    let king_rank = king.rank().index();
+    let king_file = king.file().index();
+    let forward = forward_ranks_mask(king_rank, color);
+
+    let mut score = PositionalScore::default();
+    for file in king_file.saturating_sub(1)..=(king_file + 1).min(7) {
+        let file_bb = file_mask(file);
+        score += params.shield[nearest_distance(ours & file_bb & forward, king_rank, color)];
+        score += params.storm[nearest_distance(theirs & file_bb & forward, king_rank, color)];
+        match ((ours & file_bb) != 0, (theirs & file_bb) != 0) {
+            (false, false) => score += params.king_open_file,
+            (false, true) => score += params.king_semi_open_file,
+            _ => {}
+        }
+    }
+    return score;
+}
+
+/// Computes the number of ranks between the given rank and the nearest of the given
+/// pawns, all of which are in front of the rank from the perspective of `color`.
+/// Pawns further away than [`MAX_PAWN_DISTANCE`], or no pawns at all, count as zero.
+fn nearest_distance(pawns: RawBitboard, rank: u8, color: Color) -> usize {
+    if pawns == 0 { return 0; }
+    let nearest_rank = match color {
+        Color::White => (pawns.trailing_zeros() / 8) as u8,
+        Color::Black => ((63 - pawns.leading_zeros()) / 8) as u8
+    };
+    let distance = usize::from(nearest_rank.abs_diff(rank));
+    if distance > MAX_PAWN_DISTANCE { return 0; }
+    return distance;
+}
+
+/// Computes the penalty for the enemy pieces attacking the squares surrounding the king.
+fn calc_attack_penalty(board: &Bitboards, king: StandardCoordinate, color: Color,
+    params: &EvalParams) -> i16
+{
+    let mut zone = MDBitboard::default();
+    zone.set(king);
+    let neighbors: Bitboard<StandardCS> = king_attack(king.into());
+    for pos in neighbors.scan() { zone.set(pos.into()); }
+
+    let enemy = color.oppo();
+    let mut attackers: u8 = 0;
+    let mut units: i32 = 0;
+    for species in [Species::Knight, Species::Bishop, Species::Rook, Species::Queen] {
+        let pieces: Bitboard<StandardCS> = board.class(enemy, species);
+        for origin in pieces.scan() {
+            let origin: StandardCoordinate = origin.into();
+            let hits = count_zone_attacks(board, &zone, origin, species);
+            if hits == 0 { continue; }
+            attackers += 1;
+            units += i32::from(hits) * i32::from(params.attack_units[usize::from(species.index())]);
+        }
+    }
+    if attackers < MIN_ATTACKERS { return 0; }
+    // Tuned attack units are unbounded, so the square is taken in the wider type.
+    return (units.saturating_mul(units) / 4).min(i32::from(MAX_ATTACK_PENALTY)) as i16;
+}
+
+/// Counts the squares of the king zone attacked by the given piece.
+fn count_zone_attacks(board: &Bitboards, zone: &MDBitboard, origin: StandardCoordinate,
+    species: Species) -> u8
+{
+    match species {
+        Species::Knight => (knight_attack(origin.into()) & zone.get()).count(),
+        Species::Bishop => count_lane_attacks::<ProdiagonalMajorCS>(board, zone, origin)
+            + count_lane_attacks::<AntidiagonalMajorCS>(board, zone, origin),
+        Species::Rook => count_lane_attacks::<RankMajorCS>(board, zone, origin)
+            + count_lane_attacks::<FileMajorCS>(board, zone, origin),
+        Species::Queen => count_lane_attacks::<RankMajorCS>(board, zone, origin)
+            + count_lane_attacks::<FileMajorCS>(board, zone, origin)
+            + count_lane_attacks::<ProdiagonalMajorCS>(board, zone, origin)
+            + count_lane_attacks::<AntidiagonalMajorCS>(board, zone, origin),
+        Species::Pawn | Species::King => 0
+    }
+}
+
+fn count_lane_attacks<C: CoordinateSystem>(board: &Bitboards, zone: &MDBitboard,
+    origin: StandardCoordinate) -> u8
+{
+    let lane: Bitboard<C> = lanescan(board, origin);
+    return (lane & zone.get()).count();
+}
+
+/// Scores the safety of both kings from White's perspective.
+pub fn calc_king_safety(board: &Bitboards, has_castled: &ColorTable<bool>, params: &EvalParams)
+-> PositionalScore
+{
+    let mut score = eval_king_safety(board, has_castled[Color::White], Color::White, params);
+    score -= eval_king_safety(board, has_castled[Color::Black], Color::Black, params);
+    return score;
+}
@@ -0,0 +1,112 @@
+//! Long algebraic notation, the pure coordinate move notation spoken by
+//! chess engine protocols. A move is written as its origin and destination
+//! followed by an optional promotion letter, for example `e2e4` or `e7e8q`.
+//! Castling is written as the king's two-square move, for example `e1g1`.
+
+use crate::gamestate::ChessGame;
+use crate::grid::File;
+use crate::grid::ParseStandardCoordinateError;
+use crate::grid::Rank;
+use crate::grid::Side;
+use crate::grid::StandardCoordinate;
+use crate::mov::AnyMove;
+use crate::mov::PieceMove;
+use crate::movegen::dispatch::movegen_legal;
+use crate::movegen::types::GeneratedMove;
+use crate::piece::Color;
+use crate::piece::Species;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum ParseLanErr {
+    BadLen,
+    MalformedOrigin(ParseStandardCoordinateError),
+    MalformedDestin(ParseStandardCoordinateError),
+    MalformedPromote,
+    IllegalMove(AnyMove)
+}
+
+/// Parses a move written in long algebraic notation and verifies that
+/// it is legal in the given position.
+pub fn parse_lan(state: &mut ChessGame, token: &str) -> Result<AnyMove, ParseLanErr> {
+    let token = token.trim();
+    if !token.is_ascii() { return Err(ParseLanErr::BadLen); }
+    if (token.len() < 4) | (token.len() > 5) { return Err(ParseLanErr::BadLen); }
+    let origin = StandardCoordinate::from_str(&token[0..2].to_ascii_uppercase())
+        .map_err(ParseLanErr::MalformedOrigin)?;
+    let destin = StandardCoordinate::from_str(&token[2..4].to_ascii_uppercase())
+        .map_err(ParseLanErr::MalformedDestin)?;
+    let mut promote: Option<Species> = None;
+    if let Some(ch) = token[4..].chars().next() {
+        match ch {
+            'q' | 'Q' => promote = Some(Species::Queen),
+            'n' | 'N' => promote = Some(Species::Knight),
+            'b' | 'B' => promote = Some(Species::Bishop),
+            'r' | 'R' => promote = Some(Species::Rook),
+            _ => return Err(ParseLanErr::MalformedPromote)
+        }
+    }
+    let pmove = PieceMove { origin, destin, promote };
+    let prop_move = match castle_side(state, pmove) {
+        Some(side) => AnyMove::Castle(side),
+        None => AnyMove::Piece(pmove),
+    };
+
+    let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut legal_moves);
+    let is_legal = legal_moves.iter().map(|genmove| genmove.mov)
+        .any(|mov| mov == prop_move);
+    if !is_legal { return Err(ParseLanErr::IllegalMove(prop_move)) }
+    return Ok(prop_move);
+}
+
+/// Determines if the given coordinate move is the king's half of a castling move.
+fn castle_side(state: &ChessGame, pmove: PieceMove) -> Option<Side> {
+    let piece = state.p_lut.get(pmove.origin)?;
+    if piece.species() != Species::King { return None; }
+    if pmove.origin.file() != File::E { return None; }
+    if pmove.origin.rank() != pmove.destin.rank() { return None; }
+    if pmove.destin.file() == File::G { return Some(Side::Kingside); }
+    if pmove.destin.file() == File::C { return Some(Side::Queenside); }
+    return None;
+}
+
+/// Formats the given move in long algebraic notation. The `mover` is required
+/// to resolve castling moves to the king's origin and destination squares.
+pub fn format_lan(mov: AnyMove, mover: Color) -> String {
+    let pmove = match mov {
+        AnyMove::Piece(pmove) => pmove,
+        AnyMove::Castle(side) => {
+            let base_rank = Rank::base_rank(mover);
+            let destin_file = match side {
+                Side::Queenside => File::C,
+                Side::Kingside => File::G,
+            };
+            PieceMove::new_basic(StandardCoordinate::new(base_rank, File::E),
+                StandardCoordinate::new(base_rank, destin_file))
+        }
+    };
+    let mut lan = format!("{}{}", pmove.origin, pmove.destin).to_ascii_lowercase();
+    if let Some(species) = pmove.promote {
+        lan.push(match species {
+            Species::Queen => 'q',
+            Species::Knight => 'n',
+            Species::Bishop => 'b',
+            Species::Rook => 'r',
+            other => panic!("cannot encode promotion to {:?}", other)
+        });
+    }
+    return lan;
+}
+
+/// Formats a line of play beginning with a move by `mover`, as the space-separated
+/// sequence of moves in long algebraic notation.
+pub fn format_lan_line(line: &[AnyMove], mover: Color) -> String {
+    let mut mover = mover;
+    let mut lans: Vec<String> = Vec::new();
+    for mov in line {
+        lans.push(format_lan(*mov, mover));
+        mover = mover.oppo();
+    }
+    return lans.join(" ");
+}
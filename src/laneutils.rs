@@ -9,9 +9,16 @@ use crate::sliders::get_slidescan;
 
 pub fn lanescan<C: CoordinateSystem>(board: &Bitboards, origin: StandardCoordinate)
 -> Bitboard<C>
+{
+    return lanescan_within(board.occupancy(), origin);
+}
+
+/// Computes the squares reachable by a slider at `origin` along its lane in the
+/// coordinate system `C`, given the squares which are occupied.
+pub fn lanescan_within<C: CoordinateSystem>(occupancy: Bitboard<C>, origin: StandardCoordinate)
+-> Bitboard<C>
 {
     let lane = C::get_lane(origin);
-    let occupancy: Bitboard<C> = board.occupancy();
     let base: Coordinate<C> = lane.base.into();
     let occ_bl = occupancy.copy_bitlane(base);
     let destin_bl = get_slidescan(lane.local_origin, occ_bl);
@@ -4,26 +4,46 @@
 pub mod bitboard;
 pub mod bits;
 pub mod attack;
+pub mod bench;
+pub mod bgsearch;
 pub mod cache;
+pub mod cecp;
 pub mod cli;
+pub mod clock;
 pub mod coordinates;
 pub mod crights;
 pub mod enpassant;
 pub mod eval;
+pub mod eval_params;
+pub mod fen;
 pub mod gamestate;
 pub mod grid;
+pub mod lan;
 pub mod laneutils;
 pub mod makemove;
 pub mod mat_eval;
+pub mod pos_eval;
+pub mod pawn_eval;
+pub mod king_safety;
+pub mod mobility;
+#[cfg(feature = "nnue")]
+pub mod nnue;
 pub mod misc;
 pub mod movegen;
+pub mod perft;
 pub mod persistence;
+pub mod params;
+pub mod pgn;
 pub mod piece;
 pub mod play;
 pub mod rmrel;
 pub mod repetitions;
+pub mod san;
 pub mod search;
+pub mod see;
 pub mod sliders;
+pub mod tune;
+pub mod uci;
 pub mod stdinit;
 pub mod mov;
 pub mod movesort;
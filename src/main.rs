@@ -1,11 +1,70 @@
 fn main() {
     use std::time::Duration;
+    use chess_solver_3::bench::print_bench;
+    use chess_solver_3::cache::HashChars;
+    use chess_solver_3::clock::Clock;
+    use chess_solver_3::cecp::cecp_main;
+    use chess_solver_3::fen::STARTING_FEN;
+    use chess_solver_3::gamestate::ChessGame;
+    use chess_solver_3::perft::print_divide;
     use chess_solver_3::play::selfplay;
     use chess_solver_3::play::humanplay;
     use chess_solver_3::piece::ColorTable;
-    // humanplay(Duration::from_secs(20));
-    selfplay(ColorTable::from_array([Duration::from_secs(1), Duration::from_secs(1)]));
+    use chess_solver_3::tune::run_tuner;
+    use chess_solver_3::uci::uci_main;
 
+    // The network of the NNUE evaluator is loaded before any position is set up.
+    #[cfg(feature = "nnue")]
+    if let Ok(path) = std::env::var("EVAL_FILE") {
+        if let Err(err) = chess_solver_3::nnue::load_network(&path) {
+            eprintln!("failed to load network {}: {:?}", path, err);
+        }
+    }
+
+    match std::env::args().nth(1).as_deref() {
+        Some("selfplay") => {
+            let clock = Clock { remaining: Duration::from_secs(60),
+                increment: Duration::from_secs(1), moves_to_go: None };
+            selfplay(ColorTable::from_array([clock, clock]));
+        },
+        Some("humanplay") => humanplay(Clock { remaining: Duration::from_secs(15 * 60),
+            increment: Duration::from_secs(10), moves_to_go: None }),
+        Some("bench") => {
+            // bench [depth] [threads]
+            let depth: u8 = std::env::args().nth(2).and_then(|arg| arg.parse().ok())
+                .unwrap_or(6);
+            let thread_count: usize = std::env::args().nth(3).and_then(|arg| arg.parse().ok())
+                .unwrap_or(1);
+            print_bench(depth, thread_count);
+        },
+        Some("tune") => {
+            // tune <positions> [output] [passes]
+            let args: Vec<String> = std::env::args().skip(2).collect();
+            let positions_path = args.first().expect("usage: tune <positions> [output] [passes]");
+            let output_path = args.get(1).map(String::as_str).unwrap_or("src/eval_params.rs");
+            let max_passes: u32 = args.get(2).and_then(|arg| arg.parse().ok()).unwrap_or(u32::MAX);
+            run_tuner(positions_path, output_path, max_passes);
+        },
+        Some("perft") => {
+            // perft <depth> [fen]
+            let args: Vec<String> = std::env::args().skip(2).collect();
+            let depth: u8 = args.first().and_then(|arg| arg.parse().ok())
+                .filter(|depth| *depth > 0)
+                .expect("usage: perft <depth> [fen]");
+            let fen = if args.len() > 1 { args[1..].join(" ") } else { STARTING_FEN.to_string() };
+            let mut state = ChessGame::from_fen(&fen, HashChars::new_random()).unwrap();
+            print_divide(&mut state, depth);
+        },
+        _ => {
+            // The controller's first command identifies the protocol it speaks.
+            let mut greeting = String::new();
+            std::io::stdin().read_line(&mut greeting).unwrap();
+            match greeting.trim() {
+                "xboard" => cecp_main(greeting.trim()),
+                other => uci_main(other)
+            }
+        }
+    }
 
     // let mut gstr = String::new();
     // let mut file = std::fs::File::open("debuggame.txt").unwrap();
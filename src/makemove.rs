@@ -34,6 +34,9 @@ fn clear_tile(state: &mut ChessGame, pos: StandardCoordinate) {
         unsetbit!(state.bbs.affilia_rel_bbs[piece.color()],
             relativize(pos, state.active_player()));
         state.hash.toggle_tile(pos, piece);
+        state.pos_score.remove_piece(pos, piece);
+        #[cfg(feature = "nnue")]
+        state.accumulator.remove_piece(pos, piece);
     }
     state.p_lut.set(pos, None);
     unsetbit!(state.bbs.pawn_rel_bb, 
@@ -47,6 +50,9 @@ pub fn fill_tile(state: &mut ChessGame, pos: StandardCoordinate, piece: Piece)
     state.bbs.affilia_bbs[piece.color()].set(pos);
     state.bbs.species_bbs[piece.species()].set(pos);
     state.hash.toggle_tile(pos, piece);
+    state.pos_score.add_piece(pos, piece);
+    #[cfg(feature = "nnue")]
+    state.accumulator.add_piece(pos, piece);
     
     let rel_pos = relativize(pos, state.active_player());
     setbit!(state.bbs.affilia_rel_bbs[piece.color()], rel_pos);
@@ -158,6 +164,20 @@ pub fn make_move(state: &mut ChessGame, mov: AnyMove) {
     state.hash.toggle_crights(state.crights); // restore
 }
 
+/// Passes the turn to the opponent without moving any piece. This is not a legal
+/// move in chess, but it is useful to the search as a means of estimating the
+/// strength of a position. A null move must not be made while in check, and it
+/// must be reverted with [`unmake_null_move`].
+pub fn make_null_move(state: &mut ChessGame) {
+    state.hash.toggle_ep_vuln(is_enpassant_vuln(state));
+    let prev_halfmoveclock = state.halfmoveclock;
+    state.halfmoveclock += 1;
+    state.movelog.push(MovelogEntry { prev_crights: state.crights, prev_halfmoveclock,
+        lmove: LoggedMove::Null });
+    swap_active(state);
+    state.hash.toggle_ep_vuln(is_enpassant_vuln(state));
+}
+
 // # Unmake
 
 fn unmake_pmove(state: &mut ChessGame, pmove: LoggedPieceMove) {
@@ -238,11 +258,21 @@ pub fn unmake_move(state: &mut ChessGame) {
     match last_entry.lmove {
         LoggedMove::Castle(side) => unmake_castle(state, side),
         LoggedMove::Piece(pmove) => unmake_pmove(state, pmove),
+        LoggedMove::Null => panic!("null move must be reverted by unmake_null_move")
     }
 
     state.hash.toggle_ep_vuln(is_enpassant_vuln(state));
 }
 
+pub fn unmake_null_move(state: &mut ChessGame) {
+    state.hash.toggle_ep_vuln(is_enpassant_vuln(state));
+    let last_entry = state.movelog.pop().unwrap();
+    assert_eq!(last_entry.lmove, LoggedMove::Null);
+    state.halfmoveclock = last_entry.prev_halfmoveclock;
+    swap_active(state);
+    state.hash.toggle_ep_vuln(is_enpassant_vuln(state));
+}
+
 /// Calculates the legality of a pseudo-legal move.
 /// This procedure returns `true` if the move is legal and false otherwise.
 pub fn test_pmove_legality(state: &mut ChessGame, pmove: PieceMove) -> bool {
@@ -0,0 +1,140 @@
+//! Mobility evaluation rewards pieces by the number of squares they control, and
+//! scores a handful of other features of piece activity: the bishop pair, rooks on
+//! open files and on the seventh rank, knight outposts and trapped pieces.
+//!
+//! A square counts toward mobility only when it is not occupied by a friendly piece
+//! and is not attacked by an enemy pawn, since a piece rarely wants to move there.
+
+use crate::bitboard::Bitboard;
+use crate::bitboard::MDBitboard;
+use crate::bitboard::RawBitboard;
+use crate::coordinates::AntidiagonalMajorCS;
+use crate::coordinates::CoordinateSystem;
+use crate::coordinates::FileMajorCS;
+use crate::coordinates::ProdiagonalMajorCS;
+use crate::coordinates::RankMajorCS;
+use crate::coordinates::StandardCS;
+use crate::gamestate::Bitboards;
+use crate::grid::StandardCoordinate;
+use crate::laneutils::lanescan;
+use crate::movegen::knight::knight_attack;
+use crate::params::EvalParams;
+use crate::pawn_eval::adjacent_files_mask;
+use crate::pawn_eval::file_mask;
+use crate::pawn_eval::forward_ranks_mask;
+use crate::pawn_eval::pawn_attacks;
+use crate::piece::Color;
+use crate::piece::Species;
+use crate::pos_eval::PositionalScore;
+
+// # Constants
+
+/// The typical mobility of a piece of each species, see [`EvalParams::mobility`].
+/// Indexed by [`Species::index`]: Pawn, Rook, Knight, Bishop, Queen, King.
+const TYPICAL_MOBILITY: [i16; 6] = [0, 7, 4, 7, 14, 0];
+
+/// A piece with at most this many squares to move to is considered trapped,
+/// see [`EvalParams::trapped`].
+const TRAPPED_MOBILITY: u8 = 1;
+
+// # Evaluation
+
+/// Scores the activity of the pieces of the given player, from that player's perspective.
+fn eval_activity(board: &Bitboards, color: Color, params: &EvalParams) -> PositionalScore {
+    let ours = board.class::<StandardCS>(color, Species::Pawn).raw();
+    let theirs = board.class::<StandardCS>(color.oppo(), Species::Pawn).raw();
+    let our_attacks = pawn_attacks(ours, color);
+    let their_attacks = pawn_attacks(theirs, color.oppo());
+
+    let mut unavailable: MDBitboard = board.affilia_bbs[color];
+    let their_attacks_bb: Bitboard<StandardCS> = Bitboard::from_raw(their_attacks);
+    for pos in their_attacks_bb.scan() { unavailable.set(pos.into()); }
+
+    let mut score = PositionalScore::default();
+    for species in [Species::Knight, Species::Bishop, Species::Rook, Species::Queen] {
+        let pieces: Bitboard<StandardCS> = board.class(color, species);
+        for origin in pieces.scan() {
+            let origin: StandardCoordinate = origin.into();
+            let mobility = count_mobility(board, &unavailable, origin, species);
+            let index = usize::from(species.index());
+            let excess = i16::from(mobility) - TYPICAL_MOBILITY[index];
+            score.mg += params.mobility[index].mg * excess;
+            score.eg += params.mobility[index].eg * excess;
+            if mobility <= TRAPPED_MOBILITY { score += params.trapped[index]; }
+        }
+    }
+
+    let bishops: Bitboard<StandardCS> = board.class(color, Species::Bishop);
+    if bishops.count() >= 2 { score += params.bishop_pair; }
+
+    let rooks: Bitboard<StandardCS> = board.class(color, Species::Rook);
+    for origin in rooks.scan() {
+        let origin: StandardCoordinate = origin.into();
+        let file_bb = file_mask(origin.file().index());
+        match ((ours & file_bb) != 0, (theirs & file_bb) != 0) {
+            (false, false) => score += params.rook_open_file,
+            (false, true) => score += params.rook_semi_open_file,
+            _ => {}
+        }
+        if is_on_seventh_rank(board, origin, color) { score += params.rook_seventh_rank; }
+    }
+
+    let knights: Bitboard<StandardCS> = board.class(color, Species::Knight);
+    for origin in knights.scan() {
+        let origin: StandardCoordinate = origin.into();
+        let rank = origin.rank().index();
+        let rel_rank = if color == Color::White { rank } else { 7 - rank };
+        if !(3..=5).contains(&rel_rank) { continue; }
+        let bit: RawBitboard = 1 << origin.index();
+        let is_supported = our_attacks & bit != 0;
+        let attack_files = adjacent_files_mask(origin.file().index());
+        let can_be_driven = theirs & attack_files & forward_ranks_mask(rank, color) != 0;
+        if is_supported && !can_be_driven { score += params.knight_outpost; }
+    }
+    return score;
+}
+
+/// Counts the squares which the given piece attacks and which are not `unavailable`.
+fn count_mobility(board: &Bitboards, unavailable: &MDBitboard, origin: StandardCoordinate,
+    species: Species) -> u8
+{
+    match species {
+        Species::Knight => (knight_attack(origin.into()) & !unavailable.get()).count(),
+        Species::Bishop => count_lane_mobility::<ProdiagonalMajorCS>(board, unavailable, origin)
+            + count_lane_mobility::<AntidiagonalMajorCS>(board, unavailable, origin),
+        Species::Rook => count_lane_mobility::<RankMajorCS>(board, unavailable, origin)
+            + count_lane_mobility::<FileMajorCS>(board, unavailable, origin),
+        Species::Queen => count_lane_mobility::<RankMajorCS>(board, unavailable, origin)
+            + count_lane_mobility::<FileMajorCS>(board, unavailable, origin)
+            + count_lane_mobility::<ProdiagonalMajorCS>(board, unavailable, origin)
+            + count_lane_mobility::<AntidiagonalMajorCS>(board, unavailable, origin),
+        Species::Pawn | Species::King => 0
+    }
+}
+
+fn count_lane_mobility<C: CoordinateSystem>(board: &Bitboards, unavailable: &MDBitboard,
+    origin: StandardCoordinate) -> u8
+{
+    let lane: Bitboard<C> = lanescan(board, origin);
+    return (lane & !unavailable.get()).count();
+}
+
+/// Determines if the rook is on the seventh rank (relative to its owner), and the enemy
+/// king is on the eighth rank or enemy pawns remain on the seventh.
+fn is_on_seventh_rank(board: &Bitboards, origin: StandardCoordinate, color: Color) -> bool {
+    let seventh: u8 = if color == Color::White { 6 } else { 1 };
+    let eighth: u8 = if color == Color::White { 7 } else { 0 };
+    if origin.rank().index() != seventh { return false; }
+    let enemy_pawns = board.class::<StandardCS>(color.oppo(), Species::Pawn).raw();
+    let enemy_king = board.class::<StandardCS>(color.oppo(), Species::King).raw();
+    let has_pawns = enemy_pawns & (0xFFu64 << (8 * seventh)) != 0;
+    let has_king = enemy_king & (0xFFu64 << (8 * eighth)) != 0;
+    return has_pawns | has_king;
+}
+
+/// Scores the activity of the pieces of both players from White's perspective.
+pub fn calc_mobility(board: &Bitboards, params: &EvalParams) -> PositionalScore {
+    let mut score = eval_activity(board, Color::White, params);
+    score -= eval_activity(board, Color::Black, params);
+    return score;
+}
@@ -25,6 +25,48 @@ pub enum AnyMove {
     Castle(Side)
 }
 
+/// A two-byte encoding of a move which identifies it without reference to the
+/// position, for storage in the cache. Bits 0-5 hold the origin, bits 6-11 the
+/// destination and bits 12-14 the promotion species (zero if none). Castling moves
+/// set bit 15 and hold the side in bit 0.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct EncodedMove(u16);
+
+impl EncodedMove {
+    const CASTLE_FLAG: u16 = 1 << 15;
+
+    pub fn encode(mov: AnyMove) -> Self {
+        match mov {
+            AnyMove::Piece(pmove) => {
+                let promote = pmove.promote.map(|species| species as u16).unwrap_or(0);
+                return Self(u16::from(pmove.origin.index())
+                    | (u16::from(pmove.destin.index()) << 6)
+                    | (promote << 12));
+            },
+            AnyMove::Castle(side) => return Self(Self::CASTLE_FLAG | u16::from(side.index()))
+        }
+    }
+
+    pub fn decode(self) -> AnyMove {
+        if self.0 & Self::CASTLE_FLAG != 0 {
+            let side = pick(self.0 & 1 == 1, Side::Kingside, Side::Queenside);
+            return AnyMove::Castle(side);
+        }
+        let origin = StandardCoordinate::from_index((self.0 & 0b111111) as u8);
+        let destin = StandardCoordinate::from_index(((self.0 >> 6) & 0b111111) as u8);
+        let promote = match (self.0 >> 12) as u8 {
+            0 => None,
+            species => Some(Species::from_index(species - 1))
+        };
+        return AnyMove::Piece(PieceMove { origin, destin, promote });
+    }
+
+    pub fn bits(self) -> u16 { self.0 }
+
+    /// The inverse of [`EncodedMove::bits`].
+    pub fn from_bits(bits: u16) -> Self { Self(bits) }
+}
+
 /// Computes the position of the piece captured by this move (if any).
 /// The target is identical to the destination in every case except enpassant.
 pub fn get_target_sq(pmgmove: PieceMove, state: &mut ChessGame) -> StandardCoordinate {
@@ -11,6 +11,7 @@ use crate::movegen::types::GeneratedMove;
 use crate::movegen::types::MGContext;
 
 pub fn movegen_castle(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
+    if ctx.captures_only() { return; }
     movegen_castle_queenside(ctx);
     movegen_castle_kingside(ctx);
 }
@@ -25,7 +25,16 @@ fn movegen_dispatch(ctx: &mut MGContext<impl Push<GeneratedMove>>)
 
 pub fn movegen_legal(state: &mut ChessGame, moves: &mut impl Push<GeneratedMove>) {
     let state_cell = RefCell::new(state);
-    let mut ctx = MGContext::new(&state_cell, moves);
+    let mut ctx = MGContext::new(&state_cell, moves, false);
+    movegen_dispatch(&mut ctx);
+}
+
+/// Generates the subset of legal moves which capture a piece or promote a pawn.
+/// These are the moves which can change the material balance, and so the only moves
+/// considered by quiescence search.
+pub fn movegen_captures(state: &mut ChessGame, moves: &mut impl Push<GeneratedMove>) {
+    let state_cell = RefCell::new(state);
+    let mut ctx = MGContext::new(&state_cell, moves, true);
     movegen_dispatch(&mut ctx);
 }
 
@@ -13,7 +13,7 @@ pub fn movegen_king(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
     let origin = ctx.inspect(|s| locate_king::<RankMajorCS>(&s.bbs, s.active_player()));
     
     let mut bb = king_attack(origin);
-    bb &= !ctx.inspect(|s| s.bbs.affilia_bbs[s.active_player()].get());
+    bb &= ctx.destin_mask();
     for destin in bb.scan() {
         ctx.push_p(PieceMove::new_basic(origin.into(), destin.into()));
     }
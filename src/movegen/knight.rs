@@ -17,7 +17,7 @@ pub fn movegen_knights(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
         
     for origin in knights.scan() {
         let mut destins = knight_attack(origin);
-        destins &= !ctx.inspect(|s| s.bbs.affilia_bbs[s.active_player()].get());
+        destins &= ctx.destin_mask();
         for destin in destins.scan() {
             ctx.push_p(PieceMove::new_basic(origin.into(), destin.into()));
         }
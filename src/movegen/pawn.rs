@@ -1,7 +1,7 @@
 use crate::bitboard::RawBitboard;
 use crate::bits::bitscan;
 use crate::bits::repeat_byte_u64;
-use crate::gamestate::LoggedMove;
+use crate::enpassant::locate_last_pdj;
 use crate::misc::Push;
 use crate::piece::Species;
 use crate::rmrel::{absolutize, RMRelCoord};
@@ -37,12 +37,14 @@ fn movegen_forward1(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
     for destin_rmrel in bitscan(bb & PROMOTE_MASK) {
         push_promote(ctx, destin_rmrel - 8, destin_rmrel);
     }
+    if ctx.captures_only() { return; }
     for destin_rmrel in bitscan(bb & !PROMOTE_MASK) {
         push_basic(ctx, destin_rmrel - 8, destin_rmrel);
     }
 }
 
 fn movegen_forward2(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
+    if ctx.captures_only() { return; }
     let mut bb: RawBitboard = 0;
 
     // Select the active-player's pawns.
@@ -133,22 +135,17 @@ fn movegen_capture_kingside(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
 }
 
 fn movegen_enpassant(ctx: &mut MGContext<impl Push<GeneratedMove>>) {
-    if let Some(last_entry) = ctx.inspect(|s| s.movelog.last().copied()) {
-        if let LoggedMove::Piece(pmove) = last_entry.lmove {
-            if pmove.is_pdj {
-                let target_rmrel = relativize(pmove.mgmove.destin,
-                    ctx.active_player());
-                
-                let destin_rmrel = target_rmrel + 8;
-
-                let mut bb = reverse_pawn_attack(destin_rmrel);
-                bb &= ctx.inspect(|s| s.bbs.affilia_rel_bbs[s.active_player()]);
-                bb &= ctx.inspect(|s| s.bbs.pawn_rel_bb);
-
-                for origin_rmrel in bitscan(bb) {
-                    push_basic(ctx, origin_rmrel, destin_rmrel);
-                }
-            }
+    if let Some(pdj_destin) = ctx.inspect(locate_last_pdj) {
+        let target_rmrel = relativize(pdj_destin, ctx.active_player());
+        
+        let destin_rmrel = target_rmrel + 8;
+
+        let mut bb = reverse_pawn_attack(destin_rmrel);
+        bb &= ctx.inspect(|s| s.bbs.affilia_rel_bbs[s.active_player()]);
+        bb &= ctx.inspect(|s| s.bbs.pawn_rel_bb);
+
+        for origin_rmrel in bitscan(bb) {
+            push_basic(ctx, origin_rmrel, destin_rmrel);
         }
     }
 }
@@ -24,9 +24,7 @@ fn movegen_slider<C>(ctx: &mut MGContext<impl Push<GeneratedMove>>,
 {
     let mut bb = ctx.inspect(|s| lanescan::<C>(&s.bbs, origin));
     
-    let friendly_bb = ctx.inspect(|s| 
-        s.bbs.affilia_bbs[s.active_player()].get::<C>());
-    bb &= !friendly_bb;
+    bb &= ctx.destin_mask::<C>();
     
     for destin in bb.scan() {
         ctx.push_p(PieceMove::new_basic(origin, destin.into()));
@@ -16,16 +16,31 @@ where P: Push<GeneratedMove>
 { 
     gstate: &'a RefCell<&'b mut ChessGame>,
     pmoves: &'c mut P,
-    next_gen_id: u8
+    /// When set, only captures and promotions are generated.
+    captures_only: bool
 }
 
 impl<'a, 'b, 'c, P> MGContext<'a, 'b, 'c, P>
 where P: Push<GeneratedMove>
 {
     pub fn new(gstate: &'a RefCell<&'b mut ChessGame>,
-               pmoves: &'c mut P) -> Self
+               pmoves: &'c mut P, captures_only: bool) -> Self
     {
-           Self { gstate, pmoves, next_gen_id: 9 }
+           Self { gstate, pmoves, captures_only }
+    }
+
+    pub fn captures_only(&self) -> bool { self.captures_only }
+
+    /// The squares which the active player's pieces may move to. These are the
+    /// squares occupied by the opponent when generating captures only, and
+    /// otherwise all squares not occupied by the active player.
+    pub fn destin_mask<C>(&self) -> Bitboard<C>
+    where C: CoordinateSystem
+    {
+        let state = self.gstate.borrow();
+        let active_player = state.active_player();
+        if self.captures_only { return state.bbs.affilia_bbs[active_player.oppo()].get(); }
+        return !state.bbs.affilia_bbs[active_player].get();
     }
     
     pub fn class<C>(&self, color: Color, species: Species) -> Bitboard<C> 
@@ -51,9 +66,7 @@ where P: Push<GeneratedMove>
     }
 
     pub fn push_legal(&mut self, mov: AnyMove) {
-        let gen_id = self.next_gen_id;
-        self.next_gen_id += 1;
-        self.pmoves.push(GeneratedMove { mov, gen_id });
+        self.pmoves.push(GeneratedMove { mov });
     }
 
     pub fn push_p(&mut self, mov: PieceMove) {
@@ -63,4 +76,4 @@ where P: Push<GeneratedMove>
 }
 
 #[derive(Clone, Copy, Debug)]
-pub struct GeneratedMove { pub mov: AnyMove, pub gen_id: u8 }
\ No newline at end of file
+pub struct GeneratedMove { pub mov: AnyMove }
\ No newline at end of file
@@ -3,12 +3,20 @@ use crate::gamestate::ChessGame;
 use crate::mat_eval::get_species_value;
 use crate::misc::{Push, SegVec};
 use crate::mov::{AnyMove, get_target_sq, PieceMove};
+use crate::movegen::dispatch::movegen_captures;
 use crate::movegen::dispatch::movegen_legal;
 use crate::movegen::types::GeneratedMove;
+use crate::gamestate::LoggedMove;
+use crate::piece::Color;
 use crate::piece::Species;
+use crate::see::static_exchange_eval;
 
+/// Generates the legal moves ordered such that the most promising move is popped first.
+/// The move cached for this position is tried first, then winning captures, then the
+/// quiet moves which caused cutoffs elsewhere in the search, as recorded in `history`,
+/// and finally the remaining moves. The `ply` is the distance from the root of the search.
 pub fn movegen_legal_sorted(state: &mut ChessGame, moves: &mut SegVec<GeneratedMove>,
-                            cache: &Cache)
+                            cache: &Cache, history: &MoveHistory, ply: u8)
 {
     movegen_legal(state, moves);
 
@@ -17,37 +25,74 @@ pub fn movegen_legal_sorted(state: &mut ChessGame, moves: &mut SegVec<GeneratedM
         // Note that a hash collision is possible!
         // Therefore this cache entry could be for a
         // different position than we expect, and so
-        // `bestmov_id` might exceed the bounds
-        // of th emove list.
-        let mut bestmov_id = usize::from(value.bestmov_id);
-        if bestmov_id < moves.len() {
-            let removed = moves.swap_remove(bestmov_id);
+        // the cached move is only used if it is among
+        // the legal moves of this position.
+        let cached_mov = value.bestmov.decode();
+        let position = moves.as_slice().iter()
+            .position(|genmov| genmov.mov == cached_mov);
+        if let Some(index) = position {
+            let removed = moves.swap_remove(index);
             bestmov = Some(removed);
         }
     }
 
-    moves.as_mut_slice().sort_unstable_by_key(|genmov| score_mov(state, genmov.mov));
+    let countermove = history.lookup_countermove(state);
+    // Scoring a capture requires a static exchange evaluation, so each key is
+    // computed only once.
+    moves.as_mut_slice().sort_by_cached_key(|genmov| {
+        let mov = genmov.mov;
+        let static_score = score_mov(state, mov);
+        if static_score == 4 { return (4, i32::from(score_capture(state, mov)), static_score); }
+        let mut bucket: u8 = 1;
+        if history.is_killer(mov, ply) { bucket = 3; }
+        else if countermove == Some(mov) { bucket = 2; }
+        let butterfly = history.lookup_butterfly(state.active_player(), mov);
+        return (bucket, i32::try_from(butterfly).unwrap(), static_score);
+    });
 
     if let Some(mov) = bestmov {
         moves.push(mov);
     }
 }
 
+/// Generates the captures and promotions in Most Valuable Victim, Least Valuable
+/// Attacker order, such that the most promising capture is popped first.
+pub fn movegen_captures_sorted(state: &mut ChessGame, moves: &mut SegVec<GeneratedMove>) {
+    movegen_captures(state, moves);
+    moves.as_mut_slice().sort_unstable_by_key(|genmov| score_capture(state, genmov.mov));
+}
+
+fn score_capture(state: &mut ChessGame, mov: AnyMove) -> i16 {
+    let AnyMove::Piece(pmov) = mov else { return 0 };
+    let mover = state.p_lut.get(pmov.origin).unwrap();
+    let target = get_target_sq(pmov, state);
+    let mut gain: i16 = 0;
+    if let Some(victim) = state.p_lut.get(target) {
+        gain += get_species_value(victim.species());
+    }
+    if let Some(promote) = pmov.promote {
+        gain += get_species_value(promote) - get_species_value(Species::Pawn);
+    }
+    return gain * 16 - get_species_value(mover.species());
+}
+
 // 5. Principal Variation
-// 4. Winning Captures
+// 4. Captures which do not lose material by Static Exchange Evaluation
 // 3. Castling Moves
 // 2. Advance Pawn Moves
 // 1. Other Moves
+//
+// Within the moves which are neither the principal variation nor winning captures,
+// killer moves and countermoves come first, and then the history heuristic
+// takes precedence over this static score.
 
 pub fn score_mov(state: &mut ChessGame, mov: AnyMove) -> u8 {
     match mov {
         AnyMove::Piece(pmov) => {
             let mover = state.p_lut.get(pmov.origin).unwrap();
             let target = get_target_sq(pmov, state);
-            if let Some(victim) = state.p_lut.get(target) {
-                let victim_value = get_species_value(victim.species());
-                let mover_value = get_species_value(mover.species());
-                if victim_value > mover_value {
+            if state.p_lut.get(target).is_some() {
+                if static_exchange_eval(state, pmov) >= 0 {
                     return 4;
                 }
             } else {
@@ -62,3 +107,84 @@ pub fn score_mov(state: &mut ChessGame, mov: AnyMove) -> u8 {
         }
     }
 }
+
+// # History
+
+/// The greatest value of a butterfly history entry. Once exceeded, every entry
+/// for the player is halved, such that recent cutoffs outweigh older ones.
+const BUTTERFLY_MAX: u32 = 1 << 24;
+
+/// The number of killer moves remembered for each ply.
+const KILLER_SLOTS: usize = 2;
+
+/// Records the quiet moves which caused beta cutoffs during the search, so that the
+/// same moves can be tried early in other positions. Three heuristics are kept:
+///
+/// - Killer moves are the most recent cutoff moves found at each ply. Sibling
+///   positions are often similar enough that the same move refutes them all.
+/// - The butterfly history table accumulates, for every origin and destination,
+///   how often and how deep a move between them caused a cutoff.
+/// - The countermove table records, for every move by the opponent, the
+///   move which most recently refuted it.
+///
+/// The tables are kept for the duration of an iterative deepening search, so that
+/// each iteration benefits from what was learned by the previous ones.
+pub struct MoveHistory {
+    killers: [[Option<AnyMove>; KILLER_SLOTS]; u8::MAX as usize + 1],
+    butterfly: [[[u32; 64]; 64]; 2],
+    countermoves: [[Option<AnyMove>; 64]; 64]
+}
+
+impl MoveHistory {
+    pub fn new() -> Self {
+        Self { killers: [[None; KILLER_SLOTS]; u8::MAX as usize + 1],
+            butterfly: [[[0; 64]; 64]; 2], countermoves: [[None; 64]; 64] }
+    }
+
+    pub fn is_killer(&self, mov: AnyMove, ply: u8) -> bool {
+        return self.killers[usize::from(ply)].contains(&Some(mov));
+    }
+
+    pub fn lookup_butterfly(&self, color: Color, mov: AnyMove) -> u32 {
+        let AnyMove::Piece(pmove) = mov else { return 0 };
+        let origin = usize::from(pmove.origin.index());
+        let destin = usize::from(pmove.destin.index());
+        return self.butterfly[usize::from(color.index())][origin][destin];
+    }
+
+    /// Looks up the move which last refuted the move just made by the opponent.
+    pub fn lookup_countermove(&self, state: &ChessGame) -> Option<AnyMove> {
+        let (origin, destin) = locate_last_pmove(state)?;
+        return self.countermoves[origin][destin];
+    }
+
+    /// Records that the given quiet move caused a beta cutoff in the given position,
+    /// which was searched with the given lookahead at the given distance from the root.
+    pub fn record_cutoff(&mut self, state: &ChessGame, mov: AnyMove, ply: u8, lookahead: u8) {
+        let killers = &mut self.killers[usize::from(ply)];
+        if killers[0] != Some(mov) {
+            killers.copy_within(0..KILLER_SLOTS - 1, 1);
+            killers[0] = Some(mov);
+        }
+
+        if let Some((origin, destin)) = locate_last_pmove(state) {
+            self.countermoves[origin][destin] = Some(mov);
+        }
+
+        let AnyMove::Piece(pmove) = mov else { return };
+        let table = &mut self.butterfly[usize::from(state.active_player().index())];
+        let entry = &mut table[usize::from(pmove.origin.index())]
+            [usize::from(pmove.destin.index())];
+        *entry += u32::from(lookahead) * u32::from(lookahead);
+        if *entry > BUTTERFLY_MAX {
+            table.iter_mut().flatten().for_each(|value| *value /= 2);
+        }
+    }
+}
+
+/// Locates the origin and destination of the last move, if it was a piece move.
+fn locate_last_pmove(state: &ChessGame) -> Option<(usize, usize)> {
+    let LoggedMove::Piece(lpmove) = state.movelog.last()?.lmove else { return None };
+    return Some((usize::from(lpmove.mgmove.origin.index()),
+        usize::from(lpmove.mgmove.destin.index())));
+}
@@ -0,0 +1,319 @@
+//! An efficiently updatable neural network ("NNUE") evaluator, which may replace the
+//! hand-crafted heuristic score function once a network has been trained for it.
+//! The evaluator is compiled only with the `nnue` cargo feature, and is used only
+//! once a network has been loaded with [`load_network`] and while it is enabled,
+//! see [`set_nnue_enabled`]. Otherwise the classical evaluation is used. The engine
+//! loads the network file named by the `EVAL_FILE` environment variable at startup,
+//! and the UCI and CECP front-ends can load another one at any time.
+//!
+//! # Architecture
+//!
+//! The network has 768 binary inputs, one for each combination of piece color, species
+//! and square, and a single hidden layer of [`HIDDEN_LEN`] neurons which is computed
+//! twice: once from White's perspective and once from Black's perspective. From Black's
+//! perspective the board is mirrored vertically and the colors are swapped, so that
+//! both perspectives share one set of weights. The hidden layer is passed through a
+//! clipped ReLU and the two halves, the active player's first, feed a single output.
+//!
+//! ```text
+//! (768 -> HIDDEN_LEN) x 2 -> CReLU -> 1
+//! ```
+//!
+//! Since a move changes at most four inputs, the hidden layer (the "accumulator") is
+//! maintained incrementally by [`crate::makemove::fill_tile`] and
+//! [`crate::makemove::clear_tile`]. Unmaking a move repeats the same updates in
+//! reverse, which rolls the accumulator back exactly. Each accumulator holds on to the
+//! network it was computed with, so loading another network leaves the positions which
+//! already exist consistent until they are refreshed, see [`refresh_accumulator`].
+//!
+//! # Quantization
+//!
+//! The weights of the hidden layer are stored as 16-bit integers scaled by [`QA`], and
+//! the weights of the output layer as 16-bit integers scaled by [`QB`]. The output is
+//! computed in 64-bit integers and finally scaled to centipawns by [`EVAL_SCALE`].
+//!
+//! # File Format
+//!
+//! A network file consists of a header followed by the weights, all little-endian.
+//!
+//! ```text
+//! Offset  Size                 Contents
+//! ------  -------------------  -----------------------------------------------------
+//!      0  4                    The magic bytes "CSNN"
+//!      4  4 (u32)              The format version, which is 1
+//!      8  4 (u32)              The number of hidden neurons, which must be HIDDEN_LEN
+//!     12  768 * HIDDEN_LEN * 2 Hidden weights (i16), input-major
+//!      .  HIDDEN_LEN * 2       Hidden biases (i16)
+//!      .  2 * HIDDEN_LEN * 2   Output weights (i16), active player's half first
+//!      .  2                    Output bias (i16)
+//! ```
+//!
+//! The input index of a piece, from the perspective of the player `p`, is
+//! `384 * c + 64 * s + q`, where `c` is zero if the piece belongs to `p` and one
+//! otherwise, `s` is [`Species::index`] and `q` is the index of the square (a1 is zero,
+//! h8 is 63), mirrored vertically when `p` is Black.
+
+use crate::bitboard::Bitboard;
+use crate::coordinates::StandardCS;
+use crate::eval::MAX_MATE_PLY;
+use crate::eval::MAX_SCORE;
+use crate::gamestate::Bitboards;
+use crate::gamestate::ChessGame;
+use crate::grid::StandardCoordinate;
+use crate::piece::Color;
+use crate::piece::ColorTable;
+use crate::piece::Piece;
+use crate::piece::Species;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+// # Constants
+
+pub const INPUT_LEN: usize = 768;
+pub const HIDDEN_LEN: usize = 256;
+
+/// The scale of the hidden weights and biases, and the upper bound of the clipped ReLU.
+pub const QA: i32 = 255;
+/// The scale of the output weights.
+pub const QB: i32 = 64;
+/// The network is trained to predict the result of the game from a sigmoid of its
+/// output, and this converts that output to centipawns.
+pub const EVAL_SCALE: i32 = 400;
+
+const MAGIC: &[u8; 4] = b"CSNN";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 12;
+
+/// The evaluation is kept clear of the mate scores, see [`crate::eval::is_mate_score`].
+const MAX_EVAL: i64 = (MAX_SCORE - MAX_MATE_PLY - 1) as i64;
+
+// # `Network`
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Network {
+    /// Indexed by the input index, see the [module documentation](self).
+    pub hidden_weights: Vec<[i16; HIDDEN_LEN]>,
+    pub hidden_bias: [i16; HIDDEN_LEN],
+    /// The active player's half of the hidden layer is multiplied by the first
+    /// `HIDDEN_LEN` weights, and the other half by the remainder.
+    pub output_weights: Vec<i16>,
+    pub output_bias: i16
+}
+
+#[derive(Debug)]
+pub enum LoadNetworkErr {
+    Io(std::io::Error),
+    BadMagic,
+    UnsupportedVersion(u32),
+    /// The network was trained with a different number of hidden neurons than
+    /// this build of the engine supports.
+    HiddenLenMismatch(u32),
+    /// The file is shorter or longer than its header implies.
+    WrongLength(usize)
+}
+
+/// The length of a network file in bytes, see the [module documentation](self).
+pub const fn network_file_len() -> usize {
+    return HEADER_LEN + 2 * (INPUT_LEN * HIDDEN_LEN + HIDDEN_LEN + 2 * HIDDEN_LEN + 1);
+}
+
+/// Parses a network file, see the [module documentation](self) for the format.
+pub fn parse_network(bytes: &[u8]) -> Result<Network, LoadNetworkErr> {
+    if bytes.len() < HEADER_LEN { return Err(LoadNetworkErr::WrongLength(bytes.len())); }
+    if &bytes[0..4] != MAGIC { return Err(LoadNetworkErr::BadMagic); }
+    let version = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+    if version != VERSION { return Err(LoadNetworkErr::UnsupportedVersion(version)); }
+    let hidden_len = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if hidden_len as usize != HIDDEN_LEN {
+        return Err(LoadNetworkErr::HiddenLenMismatch(hidden_len));
+    }
+    if bytes.len() != network_file_len() { return Err(LoadNetworkErr::WrongLength(bytes.len())); }
+
+    let mut values = bytes[HEADER_LEN..].chunks_exact(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]));
+    let mut hidden_weights = vec![[0i16; HIDDEN_LEN]; INPUT_LEN];
+    for weights in hidden_weights.iter_mut() {
+        for weight in weights.iter_mut() { *weight = values.next().unwrap(); }
+    }
+    let mut hidden_bias = [0i16; HIDDEN_LEN];
+    for bias in hidden_bias.iter_mut() { *bias = values.next().unwrap(); }
+    let output_weights: Vec<i16> = values.by_ref().take(2 * HIDDEN_LEN).collect();
+    let output_bias = values.next().unwrap();
+    return Ok(Network { hidden_weights, hidden_bias, output_weights, output_bias });
+}
+
+/// Formats the given network as a network file, the inverse of [`parse_network`].
+pub fn format_network(network: &Network) -> Vec<u8> {
+    let mut bytes: Vec<u8> = Vec::with_capacity(network_file_len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(HIDDEN_LEN as u32).to_le_bytes());
+    let values = network.hidden_weights.iter().flatten()
+        .chain(network.hidden_bias.iter())
+        .chain(network.output_weights.iter())
+        .chain(std::iter::once(&network.output_bias));
+    for value in values { bytes.extend_from_slice(&value.to_le_bytes()); }
+    return bytes;
+}
+
+// # Loaded Network
+
+static NETWORK: RwLock<Option<Arc<Network>>> = RwLock::new(None);
+static IS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Loads the network file at the given path, see [`install_network`].
+pub fn load_network(path: &str) -> Result<(), LoadNetworkErr> {
+    let bytes = std::fs::read(path).map_err(LoadNetworkErr::Io)?;
+    install_network(parse_network(&bytes)?);
+    return Ok(());
+}
+
+/// Makes the given network the one used by positions constructed from now on,
+/// replacing the network loaded previously (if any). Positions which already exist
+/// keep the network they were constructed with, see [`refresh_accumulator`].
+pub fn install_network(network: Network) {
+    *NETWORK.write().unwrap() = Some(Arc::new(network));
+}
+
+pub fn loaded_network() -> Option<Arc<Network>> {
+    return NETWORK.read().unwrap().clone();
+}
+
+/// Chooses between the network (if one is loaded) and the classical evaluation.
+/// Returns `true` if the choice changed.
+pub fn set_nnue_enabled(is_enabled: bool) -> bool {
+    return IS_ENABLED.swap(is_enabled, Ordering::Relaxed) != is_enabled;
+}
+
+pub fn is_nnue_enabled() -> bool { IS_ENABLED.load(Ordering::Relaxed) }
+
+// # `Accumulator`
+
+/// The hidden layer of the network from both players' perspectives, before activation.
+#[derive(Clone)]
+pub struct Accumulator {
+    pub values: ColorTable<[i16; HIDDEN_LEN]>,
+    /// The network which the values were computed with. The accumulator reflects the
+    /// position only if a network was loaded when the position was constructed.
+    pub network: Option<Arc<Network>>
+}
+
+impl Accumulator {
+    /// Constructs the accumulator of an empty board with the loaded network.
+    pub fn new() -> Self {
+        return match loaded_network() {
+            Some(network) => Self {
+                values: ColorTable::from_array([network.hidden_bias; 2]),
+                network: Some(network)
+            },
+            None => Self {
+                values: ColorTable::from_array([[0; HIDDEN_LEN]; 2]),
+                network: None
+            }
+        };
+    }
+
+    /// Computes the accumulator of the given board from scratch.
+    pub fn compute(board: &Bitboards, network: Arc<Network>) -> Self {
+        let mut acc = Self {
+            values: ColorTable::from_array([network.hidden_bias; 2]),
+            network: Some(network)
+        };
+        for color in [Color::White, Color::Black] {
+            for species_index in 0..(Species::COUNT as u8) {
+                let species = Species::from_index(species_index);
+                let pieces: Bitboard<StandardCS> = board.class(color, species);
+                for pos in pieces.scan() {
+                    acc.add_piece(pos.into(), Piece::new(color, species));
+                }
+            }
+        }
+        return acc;
+    }
+
+    /// Activates the inputs of the given piece standing on the given square.
+    /// Additions wrap, so that a later [`Accumulator::remove_piece`] restores the
+    /// accumulator exactly regardless of the weights.
+    pub fn add_piece(&mut self, pos: StandardCoordinate, piece: Piece) {
+        let Some(network) = &self.network else { return };
+        for perspective in [Color::White, Color::Black] {
+            let weights = &network.hidden_weights[input_index(perspective, pos, piece)];
+            for (value, weight) in self.values[perspective].iter_mut().zip(weights) {
+                *value = value.wrapping_add(*weight);
+            }
+        }
+    }
+
+    /// Deactivates the inputs of the given piece standing on the given square.
+    pub fn remove_piece(&mut self, pos: StandardCoordinate, piece: Piece) {
+        let Some(network) = &self.network else { return };
+        for perspective in [Color::White, Color::Black] {
+            let weights = &network.hidden_weights[input_index(perspective, pos, piece)];
+            for (value, weight) in self.values[perspective].iter_mut().zip(weights) {
+                *value = value.wrapping_sub(*weight);
+            }
+        }
+    }
+}
+
+/// Accumulators are equal when their values were computed with equal networks.
+impl PartialEq for Accumulator {
+    fn eq(&self, other: &Self) -> bool {
+        let is_same_network = match (&self.network, &other.network) {
+            (Some(ours), Some(theirs)) => Arc::ptr_eq(ours, theirs) || ours == theirs,
+            (None, None) => true,
+            _ => false
+        };
+        return is_same_network && self.values == other.values;
+    }
+}
+
+impl Eq for Accumulator {}
+
+/// Computes the index of the input which represents the given piece standing on the
+/// given square, from the perspective of the given player.
+pub fn input_index(perspective: Color, pos: StandardCoordinate, piece: Piece) -> usize {
+    let is_theirs = usize::from(piece.color() != perspective);
+    let square = match perspective {
+        Color::White => pos.index(),
+        Color::Black => pos.index() ^ 56
+    };
+    return 384 * is_theirs + 64 * usize::from(piece.species().index()) + usize::from(square);
+}
+
+/// Recomputes the accumulator of the given position with the loaded network, for
+/// positions constructed before the network was loaded.
+pub fn refresh_accumulator(state: &mut ChessGame) {
+    if let Some(network) = loaded_network() {
+        state.accumulator = Accumulator::compute(&state.bbs, network);
+    }
+}
+
+// # Inference
+
+/// Evaluates the given position with the network its accumulator was computed with,
+/// from the perspective of the active player, in centipawns. Returns `None` when no
+/// network was loaded when the position was constructed.
+pub fn nnue_eval(state: &ChessGame) -> Option<i16> {
+    let network = state.accumulator.network.as_ref()?;
+    return Some(eval_accumulator(&state.accumulator, state.active_player(), network));
+}
+
+/// Computes the output of the network given the hidden layer.
+pub fn eval_accumulator(acc: &Accumulator, active_player: Color, network: &Network) -> i16 {
+    let (ours, theirs) = network.output_weights.split_at(HIDDEN_LEN);
+    let mut output: i64 = 0;
+    output += sum_activated(&acc.values[active_player], ours);
+    output += sum_activated(&acc.values[active_player.oppo()], theirs);
+    output += i64::from(network.output_bias);
+    let score = output * i64::from(EVAL_SCALE) / i64::from(QA * QB);
+    return score.clamp(-1 * MAX_EVAL, MAX_EVAL) as i16;
+}
+
+fn sum_activated(values: &[i16; HIDDEN_LEN], weights: &[i16]) -> i64 {
+    return values.iter().zip(weights)
+        .map(|(value, weight)| i64::from(i32::from(*value).clamp(0, QA) * i32::from(*weight)))
+        .sum();
+}
@@ -0,0 +1,88 @@
+//! The weights of the heuristic score function. The weights in use are [`EVAL_PARAMS`],
+//! which are written out by the tuner, see [`crate::tune`].
+//!
+//! [`EVAL_PARAMS`]: crate::eval_params::EVAL_PARAMS
+
+use crate::pos_eval::PositionalScore;
+
+/// Tables with one entry per species are indexed by [`crate::piece::Species::index`]:
+/// Pawn, Rook, Knight, Bishop, Queen, King. Piece-square tables are laid out as the
+/// board is drawn from White's perspective, so the first entry is a8 and the last is h1.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EvalParams {
+    // # Positional Evaluation
+    pub material: [PositionalScore; 6],
+    pub mg_pst: [[i16; 64]; 6],
+    pub eg_pst: [[i16; 64]; 6],
+
+    // # Pawn Structure
+    pub doubled: PositionalScore,
+    pub isolated: PositionalScore,
+    pub backward: PositionalScore,
+    pub connected: PositionalScore,
+    /// Indexed by the rank of the passed pawn relative to its owner.
+    pub passed: [PositionalScore; 8],
+
+    // # King Safety
+    /// Indexed by the distance from the king's rank to the nearest friendly pawn in
+    /// front of the king on each of the king's file and the adjacent files.
+    /// Zero means none.
+    pub shield: [PositionalScore; 4],
+    /// Indexed by the distance from the king's rank to the nearest enemy pawn in
+    /// front of the king on each of the king's file and the adjacent files.
+    /// Zero means none.
+    pub storm: [PositionalScore; 4],
+    /// A file near the king without any pawns.
+    pub king_open_file: PositionalScore,
+    /// A file near the king without any friendly pawns, but with enemy pawns.
+    pub king_semi_open_file: PositionalScore,
+    pub castled: PositionalScore,
+    /// The number of attack units contributed by each square of the king zone attacked
+    /// by a piece of the given species.
+    pub attack_units: [i16; 6],
+
+    // # Mobility
+    /// The score of each square of mobility, relative to the typical mobility of a
+    /// piece of that species.
+    pub mobility: [PositionalScore; 6],
+    pub trapped: [PositionalScore; 6],
+    pub bishop_pair: PositionalScore,
+    pub rook_open_file: PositionalScore,
+    pub rook_semi_open_file: PositionalScore,
+    pub rook_seventh_rank: PositionalScore,
+    pub knight_outpost: PositionalScore
+}
+
+impl EvalParams {
+    /// Collects every weight in a fixed order, so that the tuner can adjust each one
+    /// without regard for its meaning.
+    pub fn weights_mut(&mut self) -> Vec<&mut i16> {
+        let mut weights: Vec<&mut i16> = Vec::new();
+        push_scores(&mut weights, &mut self.material);
+        for table in self.mg_pst.iter_mut() { weights.extend(table.iter_mut()); }
+        for table in self.eg_pst.iter_mut() { weights.extend(table.iter_mut()); }
+        push_scores(&mut weights, [&mut self.doubled, &mut self.isolated, &mut self.backward,
+            &mut self.connected]);
+        push_scores(&mut weights, &mut self.passed);
+        push_scores(&mut weights, &mut self.shield);
+        push_scores(&mut weights, &mut self.storm);
+        push_scores(&mut weights, [&mut self.king_open_file, &mut self.king_semi_open_file,
+            &mut self.castled]);
+        weights.extend(self.attack_units.iter_mut());
+        push_scores(&mut weights, &mut self.mobility);
+        push_scores(&mut weights, &mut self.trapped);
+        push_scores(&mut weights, [&mut self.bishop_pair, &mut self.rook_open_file,
+            &mut self.rook_semi_open_file, &mut self.rook_seventh_rank,
+            &mut self.knight_outpost]);
+        return weights;
+    }
+}
+
+fn push_scores<'a>(weights: &mut Vec<&'a mut i16>,
+    scores: impl IntoIterator<Item = &'a mut PositionalScore>)
+{
+    for score in scores {
+        weights.push(&mut score.mg);
+        weights.push(&mut score.eg);
+    }
+}
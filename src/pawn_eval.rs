@@ -0,0 +1,133 @@
+//! Pawn structure evaluation rewards passed and connected pawns, and penalizes doubled,
+//! isolated and backward pawns. Since the pawn structure changes far less often than
+//! the rest of the position, the score of each pawn structure is cached in a
+//! [`PawnCache`], keyed by the pawn-only hash maintained by [`IncrementalHash`].
+//!
+//! [`IncrementalHash`]: crate::cache::IncrementalHash
+
+use crate::bitboard::RawBitboard;
+use crate::coordinates::StandardCS;
+use crate::eval_params::EVAL_PARAMS;
+use crate::gamestate::Bitboards;
+use crate::gamestate::ChessGame;
+use crate::params::EvalParams;
+use crate::piece::Color;
+use crate::piece::Species;
+use crate::pos_eval::PositionalScore;
+
+// # Masks
+
+const FILE_A: RawBitboard = 0x0101010101010101;
+const FILE_H: RawBitboard = FILE_A << 7;
+
+pub fn file_mask(file: u8) -> RawBitboard { FILE_A << file }
+
+pub fn adjacent_files_mask(file: u8) -> RawBitboard {
+    let mut mask: RawBitboard = 0;
+    if file > 0 { mask |= file_mask(file - 1); }
+    if file < 7 { mask |= file_mask(file + 1); }
+    return mask;
+}
+
+/// Computes a mask of every rank strictly in front of the given rank,
+/// from the perspective of the given player.
+pub fn forward_ranks_mask(rank: u8, color: Color) -> RawBitboard {
+    match color {
+        Color::White => (!0u64).checked_shl(8 * (u32::from(rank) + 1)).unwrap_or(0),
+        Color::Black => (1u64 << (8 * rank)) - 1
+    }
+}
+
+/// Computes the squares attacked by the given pawns.
+pub fn pawn_attacks(pawns: RawBitboard, color: Color) -> RawBitboard {
+    match color {
+        Color::White => ((pawns << 7) & !FILE_H) | ((pawns << 9) & !FILE_A),
+        Color::Black => ((pawns >> 7) & !FILE_A) | ((pawns >> 9) & !FILE_H)
+    }
+}
+
+// # Evaluation
+
+/// Scores the pawn structure of the given player, from that player's perspective.
+fn eval_pawns(board: &Bitboards, color: Color, params: &EvalParams) -> PositionalScore {
+    let ours = board.class::<StandardCS>(color, Species::Pawn).raw();
+    let theirs = board.class::<StandardCS>(color.oppo(), Species::Pawn).raw();
+    let our_attacks = pawn_attacks(ours, color);
+    let their_attacks = pawn_attacks(theirs, color.oppo());
+    let phalanx = ((ours << 1) & !FILE_A) | ((ours >> 1) & !FILE_H);
+
+    let mut score = PositionalScore::default();
+    let mut remaining = ours;
+    while remaining != 0 {
+        let index = remaining.trailing_zeros() as u8;
+        remaining &= remaining - 1;
+        let bit: RawBitboard = 1 << index;
+        let (rank, file) = (index / 8, index % 8);
+        let rel_rank = if color == Color::White { rank } else { 7 - rank };
+        let forward = forward_ranks_mask(rank, color);
+        let adjacent = adjacent_files_mask(file);
+
+        // Every pawn but the foremost on its file is penalized.
+        if ours & forward & file_mask(file) != 0 { score += params.doubled; }
+        let is_passed = theirs & forward & (file_mask(file) | adjacent) == 0;
+        if is_passed { score += params.passed[usize::from(rel_rank)]; }
+        if (our_attacks | phalanx) & bit != 0 { score += params.connected; }
+
+        if ours & adjacent == 0 {
+            score += params.isolated;
+            continue;
+        }
+        // A pawn is backward when no friendly pawn on an adjacent file can come to
+        // its support, and it cannot advance without being captured.
+        let can_be_supported = ours & adjacent & !forward != 0;
+        let stop_square = match color {
+            Color::White => bit << 8,
+            Color::Black => bit >> 8
+        };
+        if !can_be_supported && (their_attacks & stop_square != 0) { score += params.backward; }
+    }
+    return score;
+}
+
+/// Scores the pawn structure of the given position from White's perspective.
+pub fn calc_pawn_structure(board: &Bitboards, params: &EvalParams) -> PositionalScore {
+    let mut score = eval_pawns(board, Color::White, params);
+    score -= eval_pawns(board, Color::Black, params);
+    return score;
+}
+
+// # `PawnCache`
+
+#[derive(Clone, Copy)]
+struct PawnCacheEntry {
+    pawn_hash: u64,
+    score: PositionalScore
+}
+
+/// The number of entries in a [`PawnCache`]. Even a long search encounters relatively
+/// few distinct pawn structures, so the pawn cache is much smaller than the [`Cache`].
+///
+/// [`Cache`]: crate::cache::Cache
+const PAWN_CACHE_LEN: usize = 1 << 14;
+
+pub struct PawnCache { vec: Vec<Option<PawnCacheEntry>> }
+
+impl PawnCache {
+    pub fn new() -> Self {
+        Self { vec: vec![None; PAWN_CACHE_LEN] }
+    }
+
+    /// Scores the pawn structure of the given position from White's perspective using
+    /// [`EVAL_PARAMS`], see [`calc_pawn_structure`]. The score is computed only if the
+    /// pawn structure is not already in the cache.
+    pub fn lookup(&mut self, state: &ChessGame) -> PositionalScore {
+        let pawn_hash = state.hash.pawn_value();
+        let slot = &mut self.vec[(pawn_hash % (PAWN_CACHE_LEN as u64)) as usize];
+        if let Some(entry) = slot {
+            if entry.pawn_hash == pawn_hash { return entry.score; }
+        }
+        let score = calc_pawn_structure(&state.bbs, &EVAL_PARAMS);
+        *slot = Some(PawnCacheEntry { pawn_hash, score });
+        return score;
+    }
+}
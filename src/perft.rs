@@ -0,0 +1,58 @@
+//! Performance test (perft), the exhaustive enumeration of the game tree to a fixed
+//! depth. Comparing leaf node counts against published values is the standard way
+//! to verify move generation, including its rarest cases such as enpassant pins
+//! and underpromotion.
+
+use crate::gamestate::ChessGame;
+use crate::lan::format_lan;
+use crate::makemove::make_move;
+use crate::makemove::unmake_move;
+use crate::mov::AnyMove;
+use crate::movegen::dispatch::count_legal_moves;
+use crate::movegen::dispatch::movegen_legal;
+use crate::movegen::types::GeneratedMove;
+
+/// Counts the leaf nodes of the legal game tree of the given depth.
+pub fn perft(state: &mut ChessGame, depth: u8) -> u64 {
+    if depth == 0 { return 1; }
+    // Bulk counting, the leaf moves need only be generated, not made.
+    if depth == 1 { return count_legal_moves(state) as u64; }
+
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut moves);
+    let mut node_count: u64 = 0;
+    for genmove in moves {
+        make_move(state, genmove.mov);
+        node_count += perft(state, depth - 1);
+        unmake_move(state);
+    }
+    return node_count;
+}
+
+/// Performs [`perft`] separately beneath each legal move, so that a discrepancy
+/// with a reference engine can be traced to the offending subtree.
+pub fn divide(state: &mut ChessGame, depth: u8) -> Vec<(AnyMove, u64)> {
+    assert!(depth > 0);
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut moves);
+    let mut subtree_counts: Vec<(AnyMove, u64)> = Vec::new();
+    for genmove in moves {
+        make_move(state, genmove.mov);
+        subtree_counts.push((genmove.mov, perft(state, depth - 1)));
+        unmake_move(state);
+    }
+    return subtree_counts;
+}
+
+/// Prints the [`divide`] of the given position in the format used by most engines,
+/// one `move: count` line per legal move followed by the total.
+pub fn print_divide(state: &mut ChessGame, depth: u8) {
+    let mover = state.active_player();
+    let mut node_count: u64 = 0;
+    for (mov, subtree_count) in divide(state, depth) {
+        println!("{}: {}", format_lan(mov, mover), subtree_count);
+        node_count += subtree_count;
+    }
+    println!();
+    println!("Nodes searched: {}", node_count);
+}
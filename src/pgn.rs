@@ -0,0 +1,474 @@
+//! Portable Game Notation, the archival format for chess games understood by
+//! virtually every chess program. A game consists of tag pairs such as
+//! `[White "Kasparov"]` followed by movetext written in Standard Algebraic
+//! Notation, which may be annotated with comments, NAGs and variations.
+
+use crate::cache::HashChars;
+use crate::fen::ParseFenErr;
+use crate::fen::STARTING_FEN;
+use crate::gamestate::ChessGame;
+use crate::gamestate::GameResult;
+use crate::gamestate::GameStatus;
+use crate::gamestate::LoggedMove;
+use crate::gamestate::status;
+use crate::makemove::make_move;
+use crate::makemove::unmake_move;
+use crate::mov::AnyMove;
+use crate::movegen::dispatch::movegen_legal;
+use crate::movegen::types::GeneratedMove;
+use crate::piece::Color;
+use crate::san::ParseSanErr;
+use crate::san::format_san;
+use crate::san::parse_san;
+use std::iter::Peekable;
+
+/// The tags which every PGN game must carry, in the order they must be written.
+pub const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black",
+    "Result"];
+
+/// The column at which exported movetext is wrapped.
+const MAX_LINE_LEN: usize = 79;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnGame {
+    /// The tag pairs in the order they appeared, excluding `Result`,
+    /// which is derived from `result` instead.
+    pub tags: Vec<(String, String)>,
+    pub mainline: PgnLine,
+    /// The outcome of the game, or `None` if the game is unfinished or the
+    /// outcome is unknown (written `*`).
+    pub result: Option<GameResult>
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PgnLine {
+    /// The comments preceding the first move of the line.
+    pub comments: Vec<String>,
+    pub moves: Vec<PgnMove>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PgnMove {
+    pub mov: AnyMove,
+    /// Numeric Annotation Glyphs, where `!` is written as `$1`, `?` as `$2`, etc.
+    pub nags: Vec<u8>,
+    /// The comments following the move.
+    pub comments: Vec<String>,
+    /// The alternatives to this move, each beginning in the position before it.
+    pub variations: Vec<PgnLine>
+}
+
+impl PgnMove {
+    pub fn new(mov: AnyMove) -> Self {
+        Self { mov, nags: Vec::new(), comments: Vec::new(), variations: Vec::new() }
+    }
+}
+
+impl PgnGame {
+    /// Describes the game which led to the given position. The result is
+    /// derived from the final position, and a `SetUp`/`FEN` tag pair is added
+    /// when the game did not begin in the standard starting position.
+    pub fn from_chess_game(state: &ChessGame) -> Self {
+        let mut initial = state.clone();
+        let mut moves: Vec<PgnMove> = Vec::new();
+        while !initial.movelog.is_empty() {
+            let mov = match initial.movelog.last().unwrap().lmove {
+                LoggedMove::Castle(side) => AnyMove::Castle(side),
+                LoggedMove::Piece(lpmove) => AnyMove::Piece(lpmove.mgmove),
+                LoggedMove::Null => panic!("null move in game history")
+            };
+            moves.push(PgnMove::new(mov));
+            unmake_move(&mut initial);
+        }
+        moves.reverse();
+
+        let mut game = PgnGame { tags: Vec::new(),
+            mainline: PgnLine { comments: Vec::new(), moves }, result: None };
+        let initial_fen = initial.to_fen();
+        if initial_fen != STARTING_FEN {
+            game.set_tag("SetUp", "1");
+            game.set_tag("FEN", &initial_fen);
+        }
+        if let GameStatus::Complete(result) = status(&mut state.clone()) {
+            game.result = Some(result);
+        }
+        return game;
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&str> {
+        return self.tags.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str());
+    }
+
+    /// Replaces the value of the given tag, or appends the tag if it is absent.
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(key, _)| key == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    /// Constructs the position in which the game begins, as given by the `FEN` tag.
+    pub fn initial_position(&self, hash_ch: HashChars) -> Result<ChessGame, ParseFenErr> {
+        let fen = self.get_tag("FEN").unwrap_or(STARTING_FEN);
+        return ChessGame::from_fen(fen, hash_ch);
+    }
+
+    /// Plays out the mainline, producing the final position of the game.
+    pub fn replay(&self, hash_ch: HashChars) -> Result<ChessGame, ReplayPgnErr> {
+        let mut state = self.initial_position(hash_ch)
+            .map_err(ReplayPgnErr::MalformedFen)?;
+        for pmove in &self.mainline.moves {
+            let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+            movegen_legal(&mut state, &mut legal_moves);
+            let is_legal = legal_moves.iter().any(|genmove| genmove.mov == pmove.mov);
+            if !is_legal { return Err(ReplayPgnErr::IllegalMove(pmove.mov)); }
+            make_move(&mut state, pmove.mov);
+        }
+        return Ok(state);
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayPgnErr {
+    MalformedFen(ParseFenErr),
+    IllegalMove(AnyMove)
+}
+
+// # Reading
+
+#[derive(Debug)]
+pub enum ReadPgnErr {
+    UnterminatedString,
+    UnterminatedComment,
+    UnexpectedChar(char),
+    MalformedTag,
+    MalformedNag(String),
+    MalformedFen(ParseFenErr),
+    /// The SAN token does not describe a legal move in the position it was played.
+    BadMove(/* token */ String, ParseSanErr),
+    /// A NAG or variation appeared before any move it could refer to.
+    OrphanAnnotation,
+    UnbalancedVariation,
+    UnexpectedToken(String)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PgnToken {
+    TagOpen,
+    TagClose,
+    VariationOpen,
+    VariationClose,
+    Period,
+    Str(String),
+    Comment(String),
+    Nag(u8),
+    /// Move numbers, SAN moves, tag names and game termination markers.
+    Symbol(String)
+}
+
+/// Reads every game in the given PGN text. Each move is checked for legality
+/// while reading, so a successfully read game can always be replayed.
+pub fn read_pgn(text: &str) -> Result<Vec<PgnGame>, ReadPgnErr> {
+    let tokens = tokenize(text)?;
+    let mut cursor = tokens.into_iter().peekable();
+    let mut games: Vec<PgnGame> = Vec::new();
+    while cursor.peek().is_some() {
+        games.push(read_game(&mut cursor)?);
+    }
+    return Ok(games);
+}
+
+fn tokenize(text: &str) -> Result<Vec<PgnToken>, ReadPgnErr> {
+    let mut tokens: Vec<PgnToken> = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut is_line_begin = true;
+    while let Some(ch) = chars.next() {
+        // A percent sign in the first column escapes the remainder of the line.
+        if is_line_begin && ch == '%' {
+            skip_line(&mut chars);
+            continue;
+        }
+        is_line_begin = ch == '\n';
+        match ch {
+            _ if ch.is_whitespace() => {},
+            '[' => tokens.push(PgnToken::TagOpen),
+            ']' => tokens.push(PgnToken::TagClose),
+            '(' => tokens.push(PgnToken::VariationOpen),
+            ')' => tokens.push(PgnToken::VariationClose),
+            '.' => tokens.push(PgnToken::Period),
+            '*' => tokens.push(PgnToken::Symbol("*".to_string())),
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|ch| *ch != '\n').collect();
+                tokens.push(PgnToken::Comment(comment.trim().to_string()));
+                is_line_begin = true;
+            },
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => comment.push(ch),
+                        None => return Err(ReadPgnErr::UnterminatedComment),
+                    }
+                }
+                tokens.push(PgnToken::Comment(comment.trim().to_string()));
+            },
+            '"' => {
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => value.push(chars.next()
+                            .ok_or(ReadPgnErr::UnterminatedString)?),
+                        Some(ch) => value.push(ch),
+                        None => return Err(ReadPgnErr::UnterminatedString),
+                    }
+                }
+                tokens.push(PgnToken::Str(value));
+            },
+            '$' => {
+                let digits = take_while_peek(&mut chars, |ch| ch.is_ascii_digit());
+                let nag = digits.parse().map_err(|_| ReadPgnErr::MalformedNag(digits))?;
+                tokens.push(PgnToken::Nag(nag));
+            },
+            '!' | '?' => {
+                let mut suffix = ch.to_string();
+                suffix.push_str(&take_while_peek(&mut chars, |ch| (ch == '!') | (ch == '?')));
+                let nag = suffix_to_nag(&suffix).ok_or(ReadPgnErr::MalformedNag(suffix))?;
+                tokens.push(PgnToken::Nag(nag));
+            },
+            _ if ch.is_ascii_alphanumeric() => {
+                let mut symbol = ch.to_string();
+                symbol.push_str(&take_while_peek(&mut chars, is_symbol_continuation));
+                tokens.push(PgnToken::Symbol(symbol));
+            },
+            other => return Err(ReadPgnErr::UnexpectedChar(other))
+        }
+    }
+    return Ok(tokens);
+}
+
+fn skip_line(chars: &mut Peekable<std::str::Chars>) {
+    for ch in chars.by_ref() {
+        if ch == '\n' { break; }
+    }
+}
+
+fn take_while_peek<F>(chars: &mut Peekable<std::str::Chars>, predicate: F) -> String
+where F: Fn(char) -> bool
+{
+    let mut taken = String::new();
+    while let Some(ch) = chars.next_if(|ch| predicate(*ch)) {
+        taken.push(ch);
+    }
+    return taken;
+}
+
+fn is_symbol_continuation(ch: char) -> bool {
+    return ch.is_ascii_alphanumeric() | "_+#=:-/".contains(ch);
+}
+
+/// Translates the traditional move suffix annotations to their equivalent NAGs.
+fn suffix_to_nag(suffix: &str) -> Option<u8> {
+    match suffix {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None
+    }
+}
+
+fn parse_result(symbol: &str) -> Option<Option<GameResult>> {
+    match symbol {
+        "1-0" => Some(Some(GameResult::Diff(Color::White))),
+        "0-1" => Some(Some(GameResult::Diff(Color::Black))),
+        "1/2-1/2" => Some(Some(GameResult::Tie)),
+        "*" => Some(None),
+        _ => None
+    }
+}
+
+fn format_result(result: Option<GameResult>) -> &'static str {
+    match result {
+        Some(GameResult::Diff(Color::White)) => "1-0",
+        Some(GameResult::Diff(Color::Black)) => "0-1",
+        Some(GameResult::Tie) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+type TokenCursor = Peekable<std::vec::IntoIter<PgnToken>>;
+
+fn read_game(cursor: &mut TokenCursor) -> Result<PgnGame, ReadPgnErr> {
+    let mut tags: Vec<(String, String)> = Vec::new();
+    let mut tag_result: Option<Option<GameResult>> = None;
+    while cursor.next_if_eq(&PgnToken::TagOpen).is_some() {
+        let Some(PgnToken::Symbol(name)) = cursor.next() else {
+            return Err(ReadPgnErr::MalformedTag) };
+        let Some(PgnToken::Str(value)) = cursor.next() else {
+            return Err(ReadPgnErr::MalformedTag) };
+        if cursor.next() != Some(PgnToken::TagClose) { return Err(ReadPgnErr::MalformedTag); }
+        if name == "Result" {
+            tag_result = parse_result(&value);
+        } else {
+            tags.push((name, value));
+        }
+    }
+
+    let mut game = PgnGame { tags, mainline: PgnLine::default(), result: None };
+    let mut state = game.initial_position(HashChars::new([0; 32]))
+        .map_err(ReadPgnErr::MalformedFen)?;
+    let termination = read_line(cursor, &mut state, &mut game.mainline, true)?;
+    game.result = termination.or(tag_result).flatten();
+    return Ok(game);
+}
+
+/// Reads the moves of a line into `line`, playing them out on `state`. The mainline
+/// ends at the game termination marker (returned) and a variation ends at its closing
+/// parenthesis. The moves of a variation are taken back before returning.
+fn read_line(cursor: &mut TokenCursor, state: &mut ChessGame, line: &mut PgnLine,
+    is_mainline: bool) -> Result<Option<Option<GameResult>>, ReadPgnErr>
+{
+    let mut termination: Option<Option<GameResult>> = None;
+    loop {
+        // A tag section without a preceding termination marker begins the next game.
+        let is_game_end = matches!(cursor.peek(), None | Some(PgnToken::TagOpen));
+        if is_game_end {
+            if !is_mainline { return Err(ReadPgnErr::UnbalancedVariation); }
+            break;
+        }
+        match cursor.next().unwrap() {
+            PgnToken::Period => {},
+            PgnToken::Comment(comment) => match line.moves.last_mut() {
+                Some(pmove) => pmove.comments.push(comment),
+                None => line.comments.push(comment),
+            },
+            PgnToken::Nag(nag) => line.moves.last_mut()
+                .ok_or(ReadPgnErr::OrphanAnnotation)?
+                .nags.push(nag),
+            PgnToken::VariationOpen => {
+                let pmove = line.moves.last_mut().ok_or(ReadPgnErr::OrphanAnnotation)?;
+                let mut variation = PgnLine::default();
+                unmake_move(state);
+                read_line(cursor, state, &mut variation, false)?;
+                make_move(state, pmove.mov);
+                pmove.variations.push(variation);
+            },
+            PgnToken::VariationClose => {
+                if is_mainline { return Err(ReadPgnErr::UnbalancedVariation); }
+                break;
+            },
+            PgnToken::Symbol(symbol) => {
+                if symbol.chars().all(|ch| ch.is_ascii_digit()) { continue; } // move number
+                if let Some(result) = parse_result(&symbol) {
+                    if !is_mainline { return Err(ReadPgnErr::UnbalancedVariation); }
+                    termination = Some(result);
+                    break;
+                }
+                let mov = parse_san(state, &symbol)
+                    .map_err(|err| ReadPgnErr::BadMove(symbol, err))?;
+                make_move(state, mov);
+                line.moves.push(PgnMove::new(mov));
+            },
+            other => return Err(ReadPgnErr::UnexpectedToken(format!("{:?}", other)))
+        }
+    }
+    if !is_mainline {
+        for _ in &line.moves { unmake_move(state); }
+    }
+    return Ok(termination);
+}
+
+// # Writing
+
+/// Writes the game in the PGN export format. The Seven Tag Roster comes first,
+/// using placeholder values for any missing tags, followed by the remaining tags.
+pub fn write_pgn<W>(stream: &mut W, game: &PgnGame) -> std::io::Result<()>
+where W: std::io::Write
+{
+    for name in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => format_result(game.result),
+            "Date" => game.get_tag(name).unwrap_or("????.??.??"),
+            _ => game.get_tag(name).unwrap_or("?")
+        };
+        write_tag(stream, name, value)?;
+    }
+    for (name, value) in &game.tags {
+        if SEVEN_TAG_ROSTER.contains(&name.as_str()) { continue; }
+        write_tag(stream, name, value)?;
+    }
+    writeln!(stream)?;
+
+    let mut state = game.initial_position(HashChars::new([0; 32]))
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput,
+            format!("{:?}", err)))?;
+    let mut tokens: Vec<String> = Vec::new();
+    format_line(&mut tokens, &mut state, &game.mainline);
+    tokens.push(format_result(game.result).to_string());
+
+    let mut line_len: usize = 0;
+    let mut prev_token = String::new();
+    for token in tokens {
+        let is_spaced = (prev_token != "(") & (token != ")");
+        if line_len > 0 {
+            if line_len + 1 + token.len() > MAX_LINE_LEN {
+                writeln!(stream)?;
+                line_len = 0;
+            } else if is_spaced {
+                write!(stream, " ")?;
+                line_len += 1;
+            }
+        }
+        write!(stream, "{}", token)?;
+        line_len += token.len();
+        prev_token = token;
+    }
+    writeln!(stream)?;
+    writeln!(stream)?;
+    return Ok(());
+}
+
+fn write_tag<W>(stream: &mut W, name: &str, value: &str) -> std::io::Result<()>
+where W: std::io::Write
+{
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(stream, "[{} \"{}\"]", name, escaped)?;
+    return Ok(());
+}
+
+/// Appends the movetext tokens of the given line, which begins in the given position.
+/// The position is restored before returning.
+fn format_line(tokens: &mut Vec<String>, state: &mut ChessGame, line: &PgnLine) {
+    for comment in &line.comments { tokens.push(format_comment(comment)); }
+    // The move number must be repeated for black after any interruption.
+    let mut is_interrupted = true;
+    for pmove in &line.moves {
+        let ply = usize::from(state.initial_ply) + state.movelog.len();
+        match state.active_player() {
+            Color::White => tokens.push(format!("{}.", ply / 2 + 1)),
+            Color::Black => if is_interrupted { tokens.push(format!("{}...", ply / 2 + 1)); },
+        }
+        tokens.push(format_san(state, pmove.mov));
+        for nag in &pmove.nags { tokens.push(format!("${}", nag)); }
+        for comment in &pmove.comments { tokens.push(format_comment(comment)); }
+        for variation in &pmove.variations {
+            tokens.push("(".to_string());
+            format_line(tokens, state, variation);
+            tokens.push(")".to_string());
+        }
+        is_interrupted = !pmove.comments.is_empty() | !pmove.variations.is_empty();
+        make_move(state, pmove.mov);
+    }
+    for _ in &line.moves { unmake_move(state); }
+}
+
+/// Braces cannot be escaped within a PGN comment, so closing braces are dropped.
+fn format_comment(comment: &str) -> String {
+    return format!("{{{}}}", comment.replace('}', ""));
+}
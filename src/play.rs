@@ -3,6 +3,9 @@ use crate::cache::HashChars;
 use crate::cli::{format_integer, print_board};
 use crate::cli::prompt_move;
 use crate::cli::prompt_ok;
+use crate::clock::Clock;
+use crate::clock::TimeManager;
+use crate::clock::calc_time_limits;
 use crate::expect_match;
 use crate::gamestate::GameResult;
 use crate::grid::File;
@@ -25,23 +28,47 @@ use crate::stdinit::new_std_chess_position;
 use crate::movegen::dispatch::count_legal_moves;
 use std::cell::RefCell;
 use std::io::Write;
+use std::sync::atomic::AtomicBool;
 use std::time::Duration;
 use std::time::Instant;
 use crate::persistence::{apply_gstr, write_move};
+use crate::pgn::PgnGame;
+use crate::pgn::write_pgn;
+use crate::san::format_san;
+use crate::san::format_san_line;
 
-pub fn automove(gstate: &mut ChessGame, think_time: Duration, cache: &mut Cache,
+/// Searches for and plays the best move, given the state of the engine's clock.
+/// The clock is charged for the time spent thinking and credited the increment.
+pub fn automove(gstate: &mut ChessGame, clock: &mut Clock, cache: &Cache,
     gamefile: &mut std::fs::File)
 {
     if matches!(status(gstate), GameStatus::Complete(_)) {
         return; }
     
+    let time_manager = TimeManager::new(calc_time_limits(clock));
+    let search_begin = Instant::now();
     let search_result = iterdeep_search(IterDeepSearchContext {
         gstate, movebuf: SegVec::new(&mut RefCell::default()),
-        deadline: Instant::now() + think_time, cache });
+        deadline: time_manager.hard_deadline(), cache, stop: &AtomicBool::new(false),
+        max_depth: u8::MAX, thread_count: 1, time_manager: Some(time_manager),
+        on_iteration: &mut |_| {} });
+    clock.remaining = clock.remaining.saturating_sub(search_begin.elapsed()) + clock.increment;
+    if let Some(moves_to_go) = clock.moves_to_go {
+        clock.moves_to_go = Some(moves_to_go.saturating_sub(1).max(1));
+    }
 
+    println!("Think Time: {} ms", search_begin.elapsed().as_millis());
+    println!("Clock: {} ms", clock.remaining.as_millis());
     println!("Depth: {} (plys considered)", search_result.depth_achieved);
     println!("Node Count: {}", format_integer(search_result.node_count));
-    println!("Best Move: {:?}", search_result.bestmove);
+    println!("Cache Usage: {}‰", search_result.hashfull);
+    println!("Best Move: {}", format_san(gstate, search_result.bestmove));
+    match search_result.mate_in {
+        Some(moves) if moves > 0 => println!("Mate In: {}", moves),
+        Some(moves) => println!("Mated In: {}", -1 * moves),
+        None => {}
+    }
+    println!("Expected Line: {}", format_san_line(gstate, &search_result.pv));
 
     write_move(gamefile, search_result.bestmove);
     gamefile.flush().unwrap();
@@ -49,9 +76,9 @@ pub fn automove(gstate: &mut ChessGame, think_time: Duration, cache: &mut Cache,
 }
 
 
-pub fn selfplay(time_constraints: ColorTable<Duration>) {
+pub fn selfplay(mut clocks: ColorTable<Clock>) {
     let mut state: ChessGame = new_std_chess_position();
-    let mut cache: Cache = Cache::new(1024 * 6);
+    let cache: Cache = Cache::new(1024 * 6);
     let mut gamefile = std::fs::File::create("../debuggame.txt").unwrap();
 
     println!("New Self-Play Game");
@@ -64,8 +91,8 @@ pub fn selfplay(time_constraints: ColorTable<Duration>) {
         println!("Legal Moves: {}", count_legal_moves(&mut state));
         println!("Crights: {:?}", state.crights);
         println!("Move #: {}", state.movelog.len() + 1);
-        let think_time = time_constraints[state.active_player()];
-        automove(&mut state, think_time, &mut cache, &mut gamefile);
+        let active_player = state.active_player();
+        automove(&mut state, &mut clocks[active_player], &cache, &mut gamefile);
         print_board(&state);
         println!("Hash: {}", state.hash.value());
         print!("\n");
@@ -79,6 +106,18 @@ pub fn selfplay(time_constraints: ColorTable<Duration>) {
         GameResult::Diff(victor) => println!("{} won", victor),
         GameResult::Tie => println!("draw"),
     }
+    archive_selfplay(&state);
+}
+
+/// Appends the finished self-play game to the PGN archive in the working directory.
+fn archive_selfplay(state: &ChessGame) {
+    let mut game = PgnGame::from_chess_game(state);
+    game.set_tag("Event", "Self-Play");
+    game.set_tag("White", env!("CARGO_PKG_NAME"));
+    game.set_tag("Black", env!("CARGO_PKG_NAME"));
+    let mut archive = std::fs::OpenOptions::new().create(true).append(true)
+        .open("selfplay.pgn").unwrap();
+    write_pgn(&mut archive, &game).unwrap();
 }
 
 pub fn humanmove(gstate: &mut ChessGame, gamefile: &mut std::fs::File) {
@@ -88,9 +127,9 @@ pub fn humanmove(gstate: &mut ChessGame, gamefile: &mut std::fs::File) {
     make_move(gstate, mov);
 }
 
-pub fn humanplay(think_time: Duration) {
+pub fn humanplay(mut engine_clock: Clock) {
     let mut state: ChessGame = new_std_chess_position();
-    let mut cache: Cache = Cache::new(1024 * 6);
+    let cache: Cache = Cache::new(1024 * 6);
     let mut gamefile = std::fs::File::create("lasthumangame.txt").unwrap();
 
     println!("New Self-Play Game");
@@ -106,7 +145,7 @@ pub fn humanplay(think_time: Duration) {
 
         match state.active_player() {
             Color::White => humanmove(&mut state, &mut gamefile),
-            Color::Black => automove(&mut state, think_time, &mut cache, &mut gamefile),
+            Color::Black => automove(&mut state, &mut engine_clock, &cache, &mut gamefile),
         }
         
         println!("Material Difference: {}", -1 * calc_matdiff(&state.bbs));
@@ -0,0 +1,143 @@
+//! Positional evaluation scores each piece by the square which it occupies, using
+//! separate piece-square tables for the middlegame and the endgame. The two scores
+//! are blended ("tapered") according to the amount of non-pawn material remaining,
+//! so that, for example, the king is encouraged to shelter in the middlegame but
+//! to centralize in the endgame. Piece values are folded into the tables, so the
+//! positional score includes the material score. All scores are in centipawns.
+//! The tables and piece values are tuned, see [`crate::params`].
+
+use crate::bitboard::Bitboard;
+use crate::coordinates::StandardCS;
+use crate::eval_params::EVAL_PARAMS;
+use crate::gamestate::Bitboards;
+use crate::gamestate::ChessGame;
+use crate::grid::StandardCoordinate;
+use crate::params::EvalParams;
+use crate::piece::Color;
+use crate::piece::Piece;
+use crate::piece::Species;
+use std::ops::AddAssign;
+use std::ops::SubAssign;
+
+// # Piece-Square Tables
+
+/// Computes the index into a piece-square table of the given piece standing on the
+/// given square. The tables are drawn with a8 first, so White's squares are mirrored.
+fn table_index(pos: StandardCoordinate, color: Color) -> usize {
+    let index = usize::from(pos.index());
+    if color == Color::White { return index ^ 56; }
+    return index;
+}
+
+// # Game Phase
+
+/// The phase of a game with all of its non-pawn material still on the board.
+/// The phase decreases toward zero as pieces are traded off.
+pub const MAX_PHASE: i16 = 24;
+
+/// Indexed by [`Species::index`]: Pawn, Rook, Knight, Bishop, Queen, King.
+const PHASE_WEIGHT: [i16; 6] = [0, 2, 1, 1, 4, 0];
+
+/// Computes the phase of the game, where [`MAX_PHASE`] is the opening and zero is
+/// a pawn endgame. Promotions could otherwise push the phase beyond the maximum.
+pub fn calc_phase(board: &Bitboards) -> i16 {
+    let mut phase: i16 = 0;
+    for species in [Species::Rook, Species::Knight, Species::Bishop, Species::Queen] {
+        let bb: Bitboard<StandardCS> = board.species_bbs[species].get();
+        phase += i16::from(bb.count()) * PHASE_WEIGHT[usize::from(species.index())];
+    }
+    return std::cmp::min(phase, MAX_PHASE);
+}
+
+// # `PositionalScore`
+
+/// The sum of the piece-square table entries of every piece on the board, from
+/// White's perspective. This is maintained incrementally by [`crate::makemove::fill_tile`]
+/// and `clear_tile`, just like the [`crate::cache::IncrementalHash`], using the tables
+/// in [`EVAL_PARAMS`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PositionalScore {
+    pub mg: i16,
+    pub eg: i16
+}
+
+impl PositionalScore {
+    pub fn add_piece(&mut self, pos: StandardCoordinate, piece: Piece) {
+        let (mg, eg) = lookup_piece_score(pos, piece, &EVAL_PARAMS);
+        self.mg += mg;
+        self.eg += eg;
+    }
+
+    pub fn remove_piece(&mut self, pos: StandardCoordinate, piece: Piece) {
+        let (mg, eg) = lookup_piece_score(pos, piece, &EVAL_PARAMS);
+        self.mg -= mg;
+        self.eg -= eg;
+    }
+}
+
+impl AddAssign for PositionalScore {
+    fn add_assign(&mut self, rhs: Self) {
+        self.mg += rhs.mg;
+        self.eg += rhs.eg;
+    }
+}
+
+impl SubAssign for PositionalScore {
+    fn sub_assign(&mut self, rhs: Self) {
+        self.mg -= rhs.mg;
+        self.eg -= rhs.eg;
+    }
+}
+
+/// Computes the middlegame and endgame scores of the given piece standing on the
+/// given square, from White's perspective.
+fn lookup_piece_score(pos: StandardCoordinate, piece: Piece, params: &EvalParams)
+-> (i16, i16)
+{
+    let species_index = usize::from(piece.species().index());
+    let table_index = table_index(pos, piece.color());
+    let material = params.material[species_index];
+    let mg = material.mg + params.mg_pst[species_index][table_index];
+    let eg = material.eg + params.eg_pst[species_index][table_index];
+    let sign = i16::from(piece.color().sign());
+    return (sign * mg, sign * eg);
+}
+
+/// Computes the [`PositionalScore`] of the given position from scratch, using the given
+/// tables. During search the incrementally maintained score is used instead.
+pub fn calc_positional_score(board: &Bitboards, params: &EvalParams) -> PositionalScore {
+    let mut score = PositionalScore::default();
+    for color in [Color::White, Color::Black] {
+        for species_index in 0..6 {
+            let species = Species::from_index(species_index);
+            let pieces: Bitboard<StandardCS> = board.class(color, species);
+            for pos in pieces.scan() {
+                let (mg, eg) = lookup_piece_score(pos.into(), Piece::new(color, species),
+                    params);
+                score.mg += mg;
+                score.eg += eg;
+            }
+        }
+    }
+    return score;
+}
+
+// # Evaluation
+
+/// Computes the tapered positional score of the given position from the perspective
+/// of the active-player.
+pub fn calc_positional_eval(state: &ChessGame) -> i16 {
+    return calc_tapered_eval(&state.bbs, state.pos_score);
+}
+
+/// Blends the middlegame and endgame components of the given score, which is from
+/// White's perspective, according to the phase of the given position. The result is
+/// from the perspective of the active-player.
+pub fn calc_tapered_eval(board: &Bitboards, score: PositionalScore) -> i16 {
+    let phase = i32::from(calc_phase(board));
+    let mg = i32::from(score.mg);
+    let eg = i32::from(score.eg);
+    let tapered = (mg * phase + eg * (i32::from(MAX_PHASE) - phase)) / i32::from(MAX_PHASE);
+    let sign = i32::from(board.active_player.sign());
+    return i16::try_from(sign * tapered).unwrap();
+}
@@ -1,5 +1,4 @@
 use crate::crights::CastlingRights;
-use crate::expect_match;
 use crate::gamestate::ChessGame;
 use crate::gamestate::LoggedMove;
 use crate::gamestate::MovelogEntry;
@@ -19,7 +18,8 @@ pub fn count_repetitions(current: &ChessGame) -> usize {
         i -= 1;
         let mov = current.movelog[i];
         if mov.prev_crights != current.crights { break; }
-        expect_match!(mov.lmove, LoggedMove::Piece(pmove));
+        // Positions preceding a null move were never actually reached by the players.
+        let LoggedMove::Piece(pmove) = mov.lmove else { break };
         if pmove.capture.is_some() { break; }
            
         unmake_move(&mut past_p_lut, pmove);
@@ -0,0 +1,242 @@
+//! Standard Algebraic Notation, the human-readable move notation used by
+//! chess players and by the PGN file format, for example `Nbd7`, `exd5`,
+//! `e8=Q+` and `O-O-O`.
+
+use crate::gamestate::ChessGame;
+use crate::grid::File;
+use crate::grid::ParseStandardCoordinateError;
+use crate::grid::Rank;
+use crate::grid::StandardCoordinate;
+use crate::grid::Side;
+use crate::makemove::inspect_move;
+use crate::makemove::make_move;
+use crate::makemove::unmake_move;
+use crate::mov::AnyMove;
+use crate::mov::PieceMove;
+use crate::mov::get_target_sq;
+use crate::movegen::dispatch::count_legal_moves;
+use crate::movegen::dispatch::movegen_legal;
+use crate::movegen::types::GeneratedMove;
+use crate::piece::Species;
+use std::fmt::Write;
+use std::str::FromStr;
+
+// # Formatting
+
+/// Formats the given legal move in Standard Algebraic Notation, including
+/// the minimal disambiguation and the check (`+`) or checkmate (`#`) suffix.
+pub fn format_san(state: &mut ChessGame, mov: AnyMove) -> String {
+    let mut san = String::new();
+    match mov {
+        AnyMove::Castle(Side::Kingside) => san.push_str("O-O"),
+        AnyMove::Castle(Side::Queenside) => san.push_str("O-O-O"),
+        AnyMove::Piece(pmove) => write_pmove_san(&mut san, state, pmove),
+    }
+    san.push_str(inspect_move(state, mov, |state| {
+        if !state.bbs.is_check() { return ""; }
+        if count_legal_moves(state) == 0 { return "#"; }
+        return "+";
+    }));
+    return san;
+}
+
+/// Formats a line of play beginning in the given position, as the space-separated
+/// sequence of moves in Standard Algebraic Notation.
+pub fn format_san_line(state: &mut ChessGame, line: &[AnyMove]) -> String {
+    let mut sans: Vec<String> = Vec::new();
+    for mov in line {
+        sans.push(format_san(state, *mov));
+        make_move(state, *mov);
+    }
+    for _ in line { unmake_move(state); }
+    return sans.join(" ");
+}
+
+fn write_pmove_san(san: &mut String, state: &mut ChessGame, pmove: PieceMove) {
+    let species = state.p_lut.get(pmove.origin).unwrap().species();
+    let target = get_target_sq(pmove, state);
+    let is_capture = state.p_lut.get(target).is_some();
+
+    if species == Species::Pawn {
+        if is_capture { san.push(get_file_letter(pmove.origin.file())); }
+    } else {
+        san.push(get_species_letter(species));
+        write_disambiguation(san, state, pmove, species);
+    }
+    if is_capture { san.push('x'); }
+    write!(san, "{}{}", get_file_letter(pmove.destin.file()), pmove.destin.rank()).unwrap();
+    if let Some(promote) = pmove.promote {
+        write!(san, "={}", get_species_letter(promote)).unwrap();
+    }
+}
+
+/// Writes the origin file, rank, or both, if another piece of the same species
+/// could also legally move to the destination.
+fn write_disambiguation(san: &mut String, state: &mut ChessGame, pmove: PieceMove,
+                        species: Species)
+{
+    let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut legal_moves);
+    let rivals: Vec<StandardCoordinate> = legal_moves.iter()
+        .filter_map(|genmove| match genmove.mov {
+            AnyMove::Piece(other) => Some(other),
+            AnyMove::Castle(_) => None,
+        })
+        .filter(|other| other.destin == pmove.destin)
+        .filter(|other| other.origin != pmove.origin)
+        .filter(|other| state.p_lut.get(other.origin).unwrap().species() == species)
+        .map(|other| other.origin)
+        .collect();
+    if rivals.is_empty() { return; }
+    let file_unique = rivals.iter().all(|o| o.file() != pmove.origin.file());
+    let rank_unique = rivals.iter().all(|o| o.rank() != pmove.origin.rank());
+    if file_unique {
+        san.push(get_file_letter(pmove.origin.file()));
+    } else if rank_unique {
+        write!(san, "{}", pmove.origin.rank()).unwrap();
+    } else {
+        write!(san, "{}{}", get_file_letter(pmove.origin.file()), pmove.origin.rank()).unwrap();
+    }
+}
+
+fn get_file_letter(file: File) -> char { file.letter().to_ascii_lowercase() }
+
+pub fn get_species_letter(species: Species) -> char {
+    match species {
+        Species::Pawn => 'P',
+        Species::Knight => 'N',
+        Species::Bishop => 'B',
+        Species::Rook => 'R',
+        Species::Queen => 'Q',
+        Species::King => 'K',
+    }
+}
+
+// # Parsing
+
+#[derive(Debug)]
+pub enum ParseSanErr {
+    Empty,
+    NotAscii,
+    MalformedSpecies(char),
+    MalformedDestin(ParseStandardCoordinateError),
+    MalformedDisambiguation,
+    MalformedPromote,
+    /// The notation is well-formed but describes no legal move.
+    IllegalMove,
+    /// The notation is well-formed but matches more than one legal move.
+    AmbiguousMove(Vec<AnyMove>)
+}
+
+/// Resolves a move written in Standard Algebraic Notation to the legal move it
+/// describes. Check and checkmate suffixes as well as move-quality annotations
+/// (`!`, `?`) are accepted but not verified. Castling may be written with
+/// either letter `O` or digit `0`.
+pub fn parse_san(state: &mut ChessGame, token: &str) -> Result<AnyMove, ParseSanErr> {
+    let token = token.trim().trim_end_matches(['+', '#', '!', '?']);
+    if token.is_empty() { return Err(ParseSanErr::Empty); }
+    if !token.is_ascii() { return Err(ParseSanErr::NotAscii); }
+
+    let prop_castle = match token {
+        "O-O" | "0-0" => Some(AnyMove::Castle(Side::Kingside)),
+        "O-O-O" | "0-0-0" => Some(AnyMove::Castle(Side::Queenside)),
+        _ => None
+    };
+
+    let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut legal_moves);
+
+    if let Some(castle) = prop_castle {
+        let is_legal = legal_moves.iter().any(|genmove| genmove.mov == castle);
+        if !is_legal { return Err(ParseSanErr::IllegalMove); }
+        return Ok(castle);
+    }
+
+    let pattern = parse_pmove_pattern(token)?;
+    let candidates: Vec<AnyMove> = legal_moves.iter()
+        .map(|genmove| genmove.mov)
+        .filter(|mov| match *mov {
+            AnyMove::Piece(pmove) => pattern.matches(state, pmove),
+            AnyMove::Castle(_) => false,
+        })
+        .collect();
+    return match candidates.len() {
+        0 => Err(ParseSanErr::IllegalMove),
+        1 => Ok(candidates[0]),
+        _ => Err(ParseSanErr::AmbiguousMove(candidates))
+    };
+}
+
+/// The constraints on a piece move expressed by a SAN token.
+struct PMovePattern {
+    species: Species,
+    origin_file: Option<File>,
+    origin_rank: Option<Rank>,
+    destin: StandardCoordinate,
+    promote: Option<Species>
+}
+
+impl PMovePattern {
+    fn matches(&self, state: &ChessGame, pmove: PieceMove) -> bool {
+        let mut matches = true;
+        matches &= state.p_lut.get(pmove.origin).unwrap().species() == self.species;
+        matches &= pmove.destin == self.destin;
+        matches &= pmove.promote == self.promote;
+        matches &= self.origin_file.is_none_or(|file| pmove.origin.file() == file);
+        matches &= self.origin_rank.is_none_or(|rank| pmove.origin.rank() == rank);
+        return matches;
+    }
+}
+
+fn parse_pmove_pattern(token: &str) -> Result<PMovePattern, ParseSanErr> {
+    let mut body = token;
+
+    // Leading piece letter, absent for pawns.
+    let mut species = Species::Pawn;
+    if let Some(letter) = body.chars().next().filter(|ch| ch.is_ascii_uppercase()) {
+        species = parse_species_letter(letter).ok_or(ParseSanErr::MalformedSpecies(letter))?;
+        body = &body[1..];
+    }
+
+    // Trailing promotion, written `=Q` or, informally, `Q`.
+    let mut promote: Option<Species> = None;
+    if let Some(letter) = body.chars().last().filter(|ch| ch.is_ascii_uppercase()) {
+        promote = Some(parse_species_letter(letter).ok_or(ParseSanErr::MalformedPromote)?);
+        body = body[..body.len() - 1].trim_end_matches('=');
+    }
+
+    let body = body.replace('x', "");
+    if body.len() < 2 { return Err(ParseSanErr::MalformedDestin(
+        ParseStandardCoordinateError::BadLen)); }
+    let (disamb, destin) = body.split_at(body.len() - 2);
+    let destin = StandardCoordinate::from_str(&destin.to_ascii_uppercase())
+        .map_err(ParseSanErr::MalformedDestin)?;
+
+    let mut origin_file: Option<File> = None;
+    let mut origin_rank: Option<Rank> = None;
+    for ch in disamb.chars() {
+        if let Ok(file) = File::from_str(&ch.to_ascii_uppercase().to_string()) {
+            if origin_file.is_some() { return Err(ParseSanErr::MalformedDisambiguation); }
+            origin_file = Some(file);
+        } else if let Ok(rank) = Rank::from_str(&ch.to_string()) {
+            if origin_rank.is_some() { return Err(ParseSanErr::MalformedDisambiguation); }
+            origin_rank = Some(rank);
+        } else {
+            return Err(ParseSanErr::MalformedDisambiguation);
+        }
+    }
+
+    return Ok(PMovePattern { species, origin_file, origin_rank, destin, promote });
+}
+
+pub fn parse_species_letter(letter: char) -> Option<Species> {
+    match letter {
+        'P' => Some(Species::Pawn),
+        'N' => Some(Species::Knight),
+        'B' => Some(Species::Bishop),
+        'R' => Some(Species::Rook),
+        'Q' => Some(Species::Queen),
+        'K' => Some(Species::King),
+        _ => None
+    }
+}
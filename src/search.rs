@@ -1,29 +1,44 @@
-use crate::cache::{Cache, CacheValue};
+use crate::cache::{Cache, CacheValue, ScoreBound};
+use crate::clock::TimeManager;
+use crate::eval::ABOVE_MAX_SCORE;
 use crate::eval::BELOW_MIN_SCORE;
 use crate::eval::DeepEvalContext;
 use crate::eval::DeepEvalException;
 use crate::eval::deep_eval;
+use crate::eval::mate_in;
 use crate::eval::shallow_eval;
 use crate::makemove::{inspect_move, make_move};
 use crate::misc::Max;
+use crate::misc::pick;
 use crate::makemove::unmake_move;
 use crate::misc::SegVec;
 use crate::movegen::dispatch::movegen_legal;
 use crate::gamestate::ChessGame;
+use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::thread::ScopedJoinHandle;
 use std::time::Instant;
 use crate::mov::AnyMove;
+use crate::mov::EncodedMove;
+use crate::movesort::MoveHistory;
+use crate::pawn_eval::PawnCache;
 use crate::movesort::movegen_legal_sorted;
 use crate::movegen::types::GeneratedMove;
 
 // # Search
 
-struct SearchContext<'a, 'b, 'c, 'd> {
+struct SearchContext<'a, 'b, 'c, 'd, 'e, 'f, 'g> {
     pub gstate: &'a mut ChessGame,
     pub lookahead: u8,
     pub movebuf: SegVec<'b, GeneratedMove>,
     pub deadline: Instant,
-    pub cache: &'c mut Cache,
+    pub cache: &'c Cache,
     pub node_count: &'d mut u64,
+    pub stop: &'e AtomicBool,
+    pub history: &'f mut MoveHistory,
+    pub pawn_cache: &'g mut PawnCache
 }
 
 pub struct DeadlineElapsed;
@@ -35,74 +50,222 @@ pub struct DeadlineElapsed;
 /// and so there **must be** an optimal move. If this procedure
 /// is called while the game is completed (there are no legal moves)
 /// it will [`panic`]. When the deadline elapses, search is cancelled and
-/// `Err(DeadlineElapsed)` is returned.
-fn search(mut ctx: SearchContext) -> Result<AnyMove, DeadlineElapsed> {
+/// `Err(DeadlineElapsed)` is returned. Otherwise the best move is returned
+/// alongside its score.
+fn search(mut ctx: SearchContext) -> Result<(AnyMove, i16), DeadlineElapsed> {
     let mut best: Max<GeneratedMove, i16> = Max::new(BELOW_MIN_SCORE);
-    movegen_legal_sorted(ctx.gstate, &mut ctx.movebuf, ctx.cache);
+    movegen_legal_sorted(ctx.gstate, &mut ctx.movebuf, ctx.cache, ctx.history, 0);
     assert!(ctx.movebuf.len() > 0);
     while let Some(genmov) = ctx.movebuf.pop() {
         let result = inspect_move(ctx.gstate, genmov.mov, |gstate| {
-            deep_eval(DeepEvalContext { gstate, lookahead: ctx.lookahead - 1,
-                movebuf: ctx.movebuf.extend(), deadline: ctx.deadline, cutoff: best.value(),
-                cache: ctx.cache, node_count: ctx.node_count })
+            deep_eval(DeepEvalContext { gstate, lookahead: ctx.lookahead - 1, ply: 1,
+                movebuf: ctx.movebuf.extend(), deadline: ctx.deadline,
+                alpha: -1 * ABOVE_MAX_SCORE, beta: -1 * best.value(),
+                cache: ctx.cache, node_count: ctx.node_count, stop: ctx.stop,
+                history: ctx.history, pawn_cache: ctx.pawn_cache })
         });
         match result {
             Err(DeepEvalException::DeadlineElapsed) => return Err(DeadlineElapsed),
-            Err(DeepEvalException::Cut) => {},
-            Ok(score) => { best.push(genmov, score * -1) }
+            Ok(score) => if score * -1 > best.value() { best.push(genmov, score * -1) }
         }
     }
-    let bestmov_id = best.item().unwrap().gen_id;
-    ctx.cache.update(ctx.gstate, ctx.lookahead, CacheValue { score: best.value(),
-        bestmov_id });
-    return Ok(best.item().unwrap().mov);
+    let bestmov = EncodedMove::encode(best.item().unwrap().mov);
+    ctx.cache.update(ctx.gstate, ctx.lookahead, 0, ScoreBound::Exact, CacheValue {
+        score: best.value(), bestmov });
+    return Ok((best.item().unwrap().mov, best.value()));
 }
 
 
-fn search_shallow(gstate: &mut ChessGame, mut movebuf: SegVec<GeneratedMove>) -> AnyMove {
+fn search_shallow(gstate: &mut ChessGame, mut movebuf: SegVec<GeneratedMove>,
+    pawn_cache: &mut PawnCache) -> (AnyMove, i16)
+{
     let mut best: Max<AnyMove, i16> = Max::new(BELOW_MIN_SCORE);
     movegen_legal(gstate, &mut movebuf); 
     while let Some(genmov) = movebuf.pop() {
         make_move(gstate, genmov.mov);
-        let score = -1 * shallow_eval(gstate);
+        let score = -1 * shallow_eval(gstate, 1, pawn_cache);
         unmake_move(gstate);
         best.push(genmov.mov, score);
     }
-    return best.item().unwrap();
+    return (best.item().unwrap(), best.value());
 }
 
 
 // # Iterative Deepening Search
 
-pub struct IterDeepSearchContext<'a, 'b, 'c> {
+pub struct IterDeepSearchContext<'a, 'b, 'c, 'd, 'e> {
     pub gstate: &'a mut ChessGame,
     pub movebuf: SegVec<'b, GeneratedMove>,
     pub deadline: Instant,
-    pub cache: &'c mut Cache
+    pub cache: &'c Cache,
+    /// Raised by another thread to cancel the search before the deadline.
+    pub stop: &'d AtomicBool,
+    /// The deepest lookahead to attempt. The search concludes early
+    /// once an iteration of this depth completes.
+    pub max_depth: u8,
+    /// The number of threads which search concurrently, including the calling
+    /// thread, see [`iterdeep_search`].
+    pub thread_count: usize,
+    /// Decides whether to begin each iteration after the first, for searches which
+    /// are timed by a game clock. Otherwise iterations continue until the deadline.
+    pub time_manager: Option<TimeManager>,
+    /// Invoked after each completed iteration with the result so-far.
+    pub on_iteration: &'e mut dyn FnMut(&IterDeepSearchResult)
 }
 
+#[derive(Clone)]
 pub struct IterDeepSearchResult {
     pub bestmove: AnyMove,
+    /// The line of play expected to follow, beginning with `bestmove`.
+    /// This line may be shorter than `depth_achieved`.
+    pub pv: Vec<AnyMove>,
+    /// The score of `bestmove` from the perspective of the active-player.
+    pub score: i16,
+    /// The number of moves until checkmate, if the search found a forced mate.
+    /// This is negative if the active-player is to be mated, see [`mate_in`].
+    pub mate_in: Option<i16>,
     pub depth_achieved: u8,
-    pub node_count: u64
+    /// The number of positions visited by every thread of the search.
+    pub node_count: u64,
+    /// The occupancy of the cache in permille, see [`Cache::hashfull`].
+    pub hashfull: u16
 }
 
 /// Conducts a time-limited search for the optimal move. 
 /// This procedure will complete at least a shallow search, regardless of 
 /// the deadline, but deeper searches are time-constrained.
+///
+/// When `thread_count` exceeds one, the search is parallelized by Lazy SMP: helper
+/// threads conduct their own iterative deepening searches of the same position, each
+/// with its own copy of the game, but all sharing the cache. The helpers do not report
+/// results, they only fill the cache with entries which the calling thread can use to
+/// search faster. Every other helper begins one ply deeper, so that the helpers
+/// diverge rather than duplicate each other's work.
 pub fn iterdeep_search(mut ctx: IterDeepSearchContext) -> IterDeepSearchResult {
-    let mut bestmove = search_shallow(ctx.gstate, ctx.movebuf.extend());
+    ctx.cache.new_generation();
+    let helper_stop = AtomicBool::new(false);
+    let helper_node_counts: Vec<AtomicU64> = (1..ctx.thread_count)
+        .map(|_| AtomicU64::new(0))
+        .collect();
+    return std::thread::scope(|scope| {
+        let helpers: Vec<ScopedJoinHandle<()>> = helper_node_counts.iter().enumerate()
+            .map(|(index, node_count)| {
+                let helper = HelperContext { gstate: ctx.gstate.clone(), cache: ctx.cache,
+                    deadline: ctx.deadline, stop: &helper_stop,
+                    first_lookahead: pick(index % 2 == 0, 2, 1), node_count };
+                scope.spawn(move || search_helper(helper))
+            })
+            .collect();
+        let mut result = search_main(&mut ctx, &helper_node_counts);
+        helper_stop.store(true, Ordering::Relaxed);
+        for helper in helpers { helper.join().unwrap(); }
+        result.node_count += sum_node_counts(&helper_node_counts);
+        return result;
+    });
+}
+
+/// Conducts the iterative deepening search on the calling thread. The node count of
+/// each reported result includes the nodes published by the helpers so-far, whereas
+/// the node count of the returned result includes only those of the calling thread.
+fn search_main(ctx: &mut IterDeepSearchContext, helper_node_counts: &[AtomicU64])
+-> IterDeepSearchResult
+{
+    let mut pawn_cache = PawnCache::new();
+    let (bestmove, score) = search_shallow(ctx.gstate, ctx.movebuf.extend(), &mut pawn_cache);
+    let mut result = IterDeepSearchResult { bestmove, pv: vec![bestmove], score,
+        mate_in: mate_in(score),
+        depth_achieved: 0, node_count: 0, hashfull: ctx.cache.hashfull() };
     let mut eval_lookahead: u8 = 1;
     let mut node_count: u64 = 0;
-    loop {
-        let result = search(SearchContext { gstate: ctx.gstate, lookahead: eval_lookahead,
+    let mut history = MoveHistory::new();
+    while eval_lookahead <= ctx.max_depth {
+        let outcome = search(SearchContext { gstate: ctx.gstate, lookahead: eval_lookahead,
             movebuf: ctx.movebuf.extend(), deadline: ctx.deadline, cache: ctx.cache,
-            node_count: &mut node_count });
-        match result {
+            node_count: &mut node_count, stop: ctx.stop, history: &mut history,
+            pawn_cache: &mut pawn_cache });
+        match outcome {
             Err(DeadlineElapsed) => break,
-            Ok(mov) => bestmove = mov,
+            Ok((bestmove, score)) => {
+                let pv = collect_pv(ctx.gstate, ctx.cache, bestmove, eval_lookahead);
+                result = IterDeepSearchResult { bestmove, pv, score, mate_in: mate_in(score),
+                    depth_achieved: eval_lookahead,
+                    node_count: node_count + sum_node_counts(helper_node_counts),
+                    hashfull: ctx.cache.hashfull() };
+                (ctx.on_iteration)(&result);
+                let should_deepen = ctx.time_manager.as_mut()
+                    .is_none_or(|time_manager| time_manager.should_deepen(&result));
+                if !should_deepen { break; }
+            }
         }
+        if eval_lookahead == u8::MAX { break; }
         eval_lookahead += 1;
     }
-    return IterDeepSearchResult { bestmove, depth_achieved: eval_lookahead, node_count };
+    result.node_count = node_count;
+    return result;
+}
+
+// # Lazy SMP
+
+struct HelperContext<'a, 'b> {
+    pub gstate: ChessGame,
+    pub cache: &'a Cache,
+    pub deadline: Instant,
+    /// Raised by the calling thread once its own search concludes.
+    pub stop: &'b AtomicBool,
+    pub first_lookahead: u8,
+    /// The number of positions visited by the helper, published after each iteration.
+    pub node_count: &'b AtomicU64
+}
+
+/// Conducts an iterative deepening search whose only product is the entries it
+/// writes into the cache, until the deadline elapses or the helper is stopped.
+fn search_helper(mut ctx: HelperContext) {
+    let mut movebuf = RefCell::default();
+    let mut movebuf = SegVec::new(&mut movebuf);
+    let mut pawn_cache = PawnCache::new();
+    let mut history = MoveHistory::new();
+    let mut node_count: u64 = 0;
+    let mut lookahead = ctx.first_lookahead;
+    loop {
+        let outcome = search(SearchContext { gstate: &mut ctx.gstate, lookahead,
+            movebuf: movebuf.extend(), deadline: ctx.deadline, cache: ctx.cache,
+            node_count: &mut node_count, stop: ctx.stop, history: &mut history,
+            pawn_cache: &mut pawn_cache });
+        ctx.node_count.store(node_count, Ordering::Relaxed);
+        if outcome.is_err() | (lookahead == u8::MAX) { break; }
+        lookahead += 1;
+    }
+}
+
+fn sum_node_counts(node_counts: &[AtomicU64]) -> u64 {
+    return node_counts.iter().map(|count| count.load(Ordering::Relaxed)).sum();
+}
+
+
+// # Principal Variation
+
+/// Reconstructs the line of play expected by the search by following the best moves
+/// recorded in the cache, up to `max_len` moves. Entries may have been overwritten, so
+/// the line can be cut short, and may belong to a colliding position, so each move
+/// is checked for legality before it is followed.
+fn collect_pv(gstate: &mut ChessGame, cache: &Cache, bestmove: AnyMove, max_len: u8)
+-> Vec<AnyMove>
+{
+    let mut pv: Vec<AnyMove> = vec![bestmove];
+    make_move(gstate, bestmove);
+    let mut visited: Vec<u64> = vec![gstate.hash.value()];
+    while pv.len() < usize::from(max_len) {
+        let Some(value) = cache.lookup_any(gstate) else { break };
+        let mov = value.bestmov.decode();
+        let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+        movegen_legal(gstate, &mut legal_moves);
+        if !legal_moves.iter().any(|genmov| genmov.mov == mov) { break; }
+        make_move(gstate, mov);
+        pv.push(mov);
+        // A repetition would otherwise be followed until `max_len`.
+        if visited.contains(&gstate.hash.value()) { break; }
+        visited.push(gstate.hash.value());
+    }
+    for _ in 0..pv.len() { unmake_move(gstate); }
+    return pv;
 }
@@ -0,0 +1,86 @@
+//! Static Exchange Evaluation (SEE) resolves the sequence of captures and recaptures
+//! on a single square which may follow a capture, without searching. At each turn of
+//! the exchange, the player to move recaptures with their least valuable attacker,
+//! or declines to recapture if doing so would lose material.
+
+use crate::attack::locate_attackers;
+use crate::bitboard::MDBitboard;
+use crate::gamestate::ChessGame;
+use crate::grid::StandardCoordinate;
+use crate::gamestate::Bitboards;
+use crate::mat_eval::get_species_value;
+use crate::mov::PieceMove;
+use crate::mov::get_target_sq;
+use crate::piece::Color;
+use crate::piece::Species;
+use std::cmp::max;
+
+/// The order in which each player commits attackers to the exchange.
+const EXCHANGE_ORDER: [Species; 6] = [Species::Pawn, Species::Knight, Species::Bishop,
+    Species::Rook, Species::Queen, Species::King];
+
+/// The value of a piece when exchanged. The king is valued greater than every other
+/// piece combined, such that it never captures onto a defended square.
+fn get_exchange_value(species: Species) -> i16 {
+    if species == Species::King { return 100; }
+    return get_species_value(species);
+}
+
+/// Computes the material gained by the active-player when the given legal move
+/// is followed by the best sequence of recaptures on the destination square.
+/// The result is negative if the move loses material. Promotions are included,
+/// but pins and checks are not considered.
+pub fn static_exchange_eval(state: &mut ChessGame, pmove: PieceMove) -> i16 {
+    let target = get_target_sq(pmove, state);
+    let mover = state.p_lut.get(pmove.origin).unwrap();
+
+    let mut gains: [i16; 33] = [0; 33];
+    gains[0] = state.p_lut.get(target).map(|p| get_exchange_value(p.species())).unwrap_or(0);
+    let mut occupant = mover.species();
+    if let Some(promote) = pmove.promote {
+        gains[0] += get_exchange_value(promote) - get_exchange_value(Species::Pawn);
+        occupant = promote;
+    }
+
+    let mut occupancy = state.bbs.affilia_bbs[Color::White];
+    occupancy |= state.bbs.affilia_bbs[Color::Black];
+    occupancy.unset(pmove.origin);
+    occupancy.unset(target);
+
+    let mut depth: usize = 0;
+    let mut color = mover.color().oppo();
+    while let Some((origin, species)) = locate_least_valuable_attacker(&state.bbs,
+        &occupancy, pmove.destin, color)
+    {
+        let gain = get_exchange_value(occupant) - gains[depth];
+        // The player declines to recapture if the opponent's last capture was already
+        // a loss for the opponent, and recapturing would not gain material even if
+        // the exchange ended immediately.
+        if max(-1 * gains[depth], gain) < 0 { break; }
+        depth += 1;
+        gains[depth] = gain;
+        occupancy.unset(origin);
+        occupant = species;
+        color = color.oppo();
+    }
+
+    // Either player may decline to recapture, so each player accepts the outcome
+    // of the recapture only when it is better than stopping the exchange.
+    while depth > 0 {
+        gains[depth - 1] = -1 * max(-1 * gains[depth - 1], gains[depth]);
+        depth -= 1;
+    }
+    return gains[0];
+}
+
+fn locate_least_valuable_attacker(board: &Bitboards, occupancy: &MDBitboard,
+    target: StandardCoordinate, color: Color) -> Option<(StandardCoordinate, Species)>
+{
+    for species in EXCHANGE_ORDER {
+        let attackers = locate_attackers(board, occupancy, target, color, species);
+        if let Some(origin) = attackers.scan().next() {
+            return Some((origin.into(), species));
+        }
+    }
+    return None;
+}
@@ -10,7 +10,13 @@ use crate::piece::Piece;
 use crate::piece::Species::*;
 
 pub fn new_std_chess_position() -> ChessGame {
-    let mut state = ChessGame::new(HashChars::new_random());
+    return std_chess_position(HashChars::new_random());
+}
+
+/// Constructs the standard starting position using the given hash characteristics.
+/// Positions which share [`HashChars`] can share a [`crate::cache::Cache`].
+pub fn std_chess_position(hash_ch: HashChars) -> ChessGame {
+    let mut state = ChessGame::new(hash_ch);
     state.hash.toggle_crights(state.crights);
     state.crights = CastlingRights::INITIAL;
     state.hash.toggle_crights(state.crights);
@@ -0,0 +1,233 @@
+//! Texel-style tuning of the evaluation parameters. Each position in a training set is
+//! labeled with the result of the game from which it was taken. The static evaluation
+//! of a position is mapped to an expected result by a sigmoid, and the weights of the
+//! [`EvalParams`] are adjusted one at a time (local search) for as long as doing so
+//! reduces the mean squared error between the expected and actual results. The tuned
+//! parameters are written out as Rust source, which replaces `src/eval_params.rs`.
+//!
+//! The static evaluation does not resolve captures, so the training positions should
+//! be quiet, for example positions taken from the leaves of a quiescence search.
+
+use crate::cache::HashChars;
+use crate::eval::calc_static_eval;
+use crate::eval_params::EVAL_PARAMS;
+use crate::fen::ParseFenErr;
+use crate::gamestate::Bitboards;
+use crate::gamestate::ChessGame;
+use crate::params::EvalParams;
+use crate::piece::ColorTable;
+use crate::pos_eval::PositionalScore;
+use std::fmt::Write;
+use std::thread::available_parallelism;
+
+// # Training Set
+
+pub struct LabeledPosition {
+    pub bbs: Bitboards,
+    pub has_castled: ColorTable<bool>,
+    /// The result of the game from which the position was taken, from White's
+    /// perspective: 1 for a win, 0.5 for a draw and 0 for a loss.
+    pub result: f64
+}
+
+#[derive(Debug)]
+pub enum ParseLabeledPositionErr {
+    MalformedFen(ParseFenErr),
+    /// The line does not contain a game result, see [`parse_labeled_position`].
+    MissingResult
+}
+
+/// Parses a line consisting of a FEN string followed by the result of the game, either
+/// as in PGN (`1-0`, `0-1` or `1/2-1/2`) or as a decimal (`[1.0]`, `[0.5]` or `[0.0]`).
+/// Anything else on the line is ignored, so EPD-style lines such as `<fen> c9 "1-0";`
+/// are accepted. The halfmove clock and fullmove number may be omitted from the FEN.
+pub fn parse_labeled_position(line: &str, hash_ch: &HashChars)
+-> Result<LabeledPosition, ParseLabeledPositionErr>
+{
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let counters = fields.iter().skip(4).take(2)
+        .take_while(|field| field.parse::<u16>().is_ok())
+        .count();
+    let fen = fields.iter().take(4 + counters).copied().collect::<Vec<&str>>().join(" ");
+    let remainder = fields[(4 + counters).min(fields.len())..].join(" ");
+
+    let result = if remainder.contains("1/2-1/2") || remainder.contains("[0.5]") { 0.5 }
+        else if remainder.contains("1-0") || remainder.contains("[1.0]") { 1.0 }
+        else if remainder.contains("0-1") || remainder.contains("[0.0]") { 0.0 }
+        else { return Err(ParseLabeledPositionErr::MissingResult); };
+
+    let state = ChessGame::from_fen(&fen, hash_ch.clone())
+        .map_err(ParseLabeledPositionErr::MalformedFen)?;
+    return Ok(LabeledPosition { bbs: state.bbs, has_castled: state.has_castled, result });
+}
+
+// # Error
+
+/// Computes the static evaluation of the given position from White's perspective.
+fn eval_white(position: &LabeledPosition, params: &EvalParams) -> f64 {
+    let score = calc_static_eval(&position.bbs, &position.has_castled, params);
+    let sign = position.bbs.active_player.sign();
+    return f64::from(score) * f64::from(sign);
+}
+
+/// Maps a score in centipawns to the expected result of the game, from 0 to 1.
+/// The scaling constant `k` depends upon the training set, see [`find_scaling_constant`].
+fn sigmoid(score: f64, k: f64) -> f64 {
+    return 1.0 / (1.0 + f64::powf(10.0, -1.0 * k * score / 400.0));
+}
+
+/// Computes the mean squared error between the expected and actual results of the
+/// given positions. The positions are divided among all of the available cores.
+pub fn calc_error(positions: &[LabeledPosition], params: &EvalParams, k: f64) -> f64 {
+    if positions.is_empty() { return 0.0; }
+    let thread_count = available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let chunk_len = positions.len().div_ceil(thread_count);
+    let total: f64 = std::thread::scope(|scope| {
+        let handles: Vec<_> = positions.chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || chunk.iter()
+                .map(|position| (position.result - sigmoid(eval_white(position, params), k))
+                    .powi(2))
+                .sum::<f64>()))
+            .collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).sum()
+    });
+    return total / positions.len() as f64;
+}
+
+/// Finds the scaling constant of the sigmoid which minimizes the error of the given
+/// parameters, by golden-section search. The constant is fixed for the remainder of
+/// tuning, so that the tuner adjusts the weights rather than the scale.
+pub fn find_scaling_constant(positions: &[LabeledPosition], params: &EvalParams) -> f64 {
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (0.0, 4.0);
+    for _ in 0..40 {
+        let left = high - ratio * (high - low);
+        let right = low + ratio * (high - low);
+        if calc_error(positions, params, left) < calc_error(positions, params, right) {
+            high = right;
+        } else {
+            low = left;
+        }
+    }
+    return (low + high) / 2.0;
+}
+
+// # Local Search
+
+/// Adjusts each weight of `params` by one centipawn in whichever direction reduces the
+/// error, repeating until no single adjustment helps or `max_passes` passes elapse.
+/// After each pass, `on_pass` is invoked with the number of the pass and the error.
+/// The error of the tuned parameters is returned.
+pub fn tune(positions: &[LabeledPosition], params: &mut EvalParams, k: f64, max_passes: u32,
+    on_pass: &mut dyn FnMut(u32, f64, &EvalParams)) -> f64
+{
+    let mut best_error = calc_error(positions, params, k);
+    let weight_count = params.weights_mut().len();
+    for pass in 1..=max_passes {
+        let mut improved = false;
+        for index in 0..weight_count {
+            for delta in [1, -1] {
+                *params.weights_mut()[index] += delta;
+                let error = calc_error(positions, params, k);
+                if error < best_error {
+                    best_error = error;
+                    improved = true;
+                    break;
+                }
+                *params.weights_mut()[index] -= delta;
+            }
+        }
+        on_pass(pass, best_error, params);
+        if !improved { break; }
+    }
+    return best_error;
+}
+
+// # Output
+
+/// Formats the given parameters as the Rust source of `src/eval_params.rs`.
+pub fn format_params_source(params: &EvalParams) -> String {
+    let mut src = String::new();
+    src.push_str("//! The evaluation parameters, as written by the tuner. This file is generated by\n");
+    src.push_str("//! `chess-solver-3 tune`, see [`crate::tune`], and so should not be edited by hand.\n\n");
+    src.push_str("use crate::params::EvalParams;\nuse crate::pos_eval::PositionalScore;\n\n");
+    src.push_str("pub static EVAL_PARAMS: EvalParams = EvalParams {\n");
+    write_scores(&mut src, "material", &params.material);
+    write_tables(&mut src, "mg_pst", &params.mg_pst);
+    write_tables(&mut src, "eg_pst", &params.eg_pst);
+    write_score(&mut src, "doubled", params.doubled);
+    write_score(&mut src, "isolated", params.isolated);
+    write_score(&mut src, "backward", params.backward);
+    write_score(&mut src, "connected", params.connected);
+    write_scores(&mut src, "passed", &params.passed);
+    write_scores(&mut src, "shield", &params.shield);
+    write_scores(&mut src, "storm", &params.storm);
+    write_score(&mut src, "king_open_file", params.king_open_file);
+    write_score(&mut src, "king_semi_open_file", params.king_semi_open_file);
+    write_score(&mut src, "castled", params.castled);
+    writeln!(src, "    attack_units: {:?},", params.attack_units).unwrap();
+    write_scores(&mut src, "mobility", &params.mobility);
+    write_scores(&mut src, "trapped", &params.trapped);
+    write_score(&mut src, "bishop_pair", params.bishop_pair);
+    write_score(&mut src, "rook_open_file", params.rook_open_file);
+    write_score(&mut src, "rook_semi_open_file", params.rook_semi_open_file);
+    write_score(&mut src, "rook_seventh_rank", params.rook_seventh_rank);
+    write_score(&mut src, "knight_outpost", params.knight_outpost);
+    src.push_str("};\n");
+    return src;
+}
+
+fn format_score(score: PositionalScore) -> String {
+    return format!("PositionalScore {{ mg: {}, eg: {} }}", score.mg, score.eg);
+}
+
+fn write_score(src: &mut String, name: &str, score: PositionalScore) {
+    writeln!(src, "    {}: {},", name, format_score(score)).unwrap();
+}
+
+fn write_scores(src: &mut String, name: &str, scores: &[PositionalScore]) {
+    writeln!(src, "    {}: [", name).unwrap();
+    for score in scores { writeln!(src, "        {},", format_score(*score)).unwrap(); }
+    src.push_str("    ],\n");
+}
+
+fn write_tables(src: &mut String, name: &str, tables: &[[i16; 64]; 6]) {
+    writeln!(src, "    {}: [", name).unwrap();
+    for table in tables {
+        src.push_str("        [\n");
+        for rank in table.chunks(8) {
+            let entries: Vec<String> = rank.iter().map(|entry| entry.to_string()).collect();
+            writeln!(src, "            {},", entries.join(", ")).unwrap();
+        }
+        src.push_str("        ],\n");
+    }
+    src.push_str("    ],\n");
+}
+
+// # Command Line
+
+/// Tunes [`EVAL_PARAMS`] against the labeled positions in the file at `positions_path`,
+/// one per line, see [`parse_labeled_position`]. The tuned parameters are written to
+/// `output_path` after every pass, so that tuning may be interrupted at any time.
+pub fn run_tuner(positions_path: &str, output_path: &str, max_passes: u32) {
+    let text = std::fs::read_to_string(positions_path).unwrap();
+    let hash_ch = HashChars::new([0; 32]);
+    let mut positions: Vec<LabeledPosition> = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() { continue; }
+        match parse_labeled_position(line, &hash_ch) {
+            Ok(position) => positions.push(position),
+            Err(err) => println!("Skipping line {}: {:?}", line_index + 1, err)
+        }
+    }
+    println!("Positions: {}", positions.len());
+
+    let mut params = EVAL_PARAMS.clone();
+    let k = find_scaling_constant(&positions, &params);
+    println!("Scaling Constant: {:.4}", k);
+    println!("Initial Error: {:.6}", calc_error(&positions, &params, k));
+    tune(&positions, &mut params, k, max_passes, &mut |pass, error, params| {
+        println!("Pass {}: Error {:.6}", pass, error);
+        std::fs::write(output_path, format_params_source(params)).unwrap();
+    });
+}
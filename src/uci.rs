@@ -0,0 +1,325 @@
+//! A front-end for the Universal Chess Interface protocol, allowing the engine
+//! to be driven by standard graphical interfaces and match runners.
+
+use crate::bgsearch::BackgroundSearch;
+use crate::bgsearch::BackgroundSearchParams;
+use crate::bgsearch::DriverEvent;
+use crate::bgsearch::spawn_input_reader;
+use crate::bgsearch::spawn_search;
+use crate::cache::Cache;
+use crate::cache::HashChars;
+use crate::clock::Clock;
+use crate::clock::TimeManager;
+use crate::clock::calc_time_limits;
+use crate::eval::to_centipawns;
+use crate::gamestate::ChessGame;
+use crate::lan::format_lan;
+use crate::lan::format_lan_line;
+use crate::lan::parse_lan;
+use crate::makemove::make_move;
+use crate::movegen::dispatch::count_legal_moves;
+#[cfg(feature = "nnue")]
+use crate::nnue::load_network;
+#[cfg(feature = "nnue")]
+use crate::nnue::refresh_accumulator;
+#[cfg(feature = "nnue")]
+use crate::nnue::set_nnue_enabled;
+use crate::perft::print_divide;
+use crate::piece::Color;
+use crate::search::IterDeepSearchResult;
+use crate::stdinit::std_chess_position;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+use std::time::Instant;
+
+const DEFAULT_HASH_MB: u64 = 64;
+const MAX_HASH_MB: u64 = 1024 * 64;
+const MAX_THREADS: usize = 256;
+
+/// Reads UCI commands from standard input and answers them on standard output
+/// until the controller sends `quit` or closes the input. The `greeting` is the
+/// first command, which has already been consumed by the caller.
+pub fn uci_main(greeting: &str) {
+    let (events, receiver) = channel();
+    spawn_input_reader(events.clone());
+    let mut engine = UciEngine::new(events);
+    if !engine.handle_command(greeting) { return; }
+    for event in receiver {
+        match event {
+            DriverEvent::Input(line) => if !engine.handle_command(&line) { break; },
+            DriverEvent::InputClosed => break,
+            DriverEvent::SearchComplete(id, result) => engine.handle_search_complete(id, result),
+        }
+    }
+    engine.halt_search();
+}
+
+struct UciEngine {
+    gstate: ChessGame,
+    hash_ch: HashChars,
+    cache: Arc<Cache>,
+    thread_count: usize,
+    events: Sender<DriverEvent>,
+    search: Option<BackgroundSearch>,
+    next_search_id: u64,
+    /// Set while an infinite search is running. The result of an infinite
+    /// search is withheld until the controller sends `stop`.
+    infinite: bool,
+    withheld: Option<IterDeepSearchResult>,
+    /// The active player of the position most recently searched. The controller may
+    /// set up another position before the result of the search is reported.
+    mover: Color
+}
+
+impl UciEngine {
+    fn new(events: Sender<DriverEvent>) -> Self {
+        let hash_ch = HashChars::new_random();
+        Self {
+            gstate: std_chess_position(hash_ch.clone()),
+            hash_ch,
+            cache: Arc::new(Cache::new(DEFAULT_HASH_MB)),
+            thread_count: 1,
+            events,
+            search: None,
+            next_search_id: 0,
+            infinite: false,
+            withheld: None,
+            mover: Color::White
+        }
+    }
+
+    /// Processes a single line of input. Returns `false` once the
+    /// controller has asked the engine to quit.
+    fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(command) = tokens.first() else { return true };
+        match *command {
+            "uci" => {
+                println!("id name {}", env!("CARGO_PKG_NAME"));
+                println!("id author duncpro");
+                println!("option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_HASH_MB, MAX_HASH_MB);
+                println!("option name Threads type spin default 1 min 1 max {}", MAX_THREADS);
+                #[cfg(feature = "nnue")]
+                {
+                    println!("option name EvalFile type string default <empty>");
+                    println!("option name UseNNUE type check default true");
+                }
+                println!("uciok");
+            },
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                self.halt_search();
+                self.cache.clear();
+                self.gstate = std_chess_position(self.hash_ch.clone());
+            },
+            "setoption" => self.handle_setoption(&tokens[1..]),
+            "position" => self.handle_position(&tokens[1..]),
+            "go" => self.handle_go(&tokens[1..]),
+            "stop" => {
+                self.infinite = false;
+                if let Some(search) = &self.search { search.stop(); }
+                if let Some(result) = self.withheld.take() { self.report_bestmove(result); }
+            },
+            "quit" => return false,
+            _ => {}
+        }
+        return true;
+    }
+
+    fn handle_setoption(&mut self, tokens: &[&str]) {
+        // setoption name <id> [value <x>]
+        let Some((&"name", tokens)) = tokens.split_first() else { return };
+        let value_pos = tokens.iter().position(|t| *t == "value");
+        let name = tokens[..value_pos.unwrap_or(tokens.len())].join(" ");
+        let value = value_pos.map(|i| tokens[i + 1..].join(" "));
+        if name.eq_ignore_ascii_case("Hash") {
+            let Some(Ok(mem_capacity)) = value.as_ref().map(|v| v.parse::<u64>()) else {
+                println!("info string malformed Hash value");
+                return;
+            };
+            self.halt_search();
+            let mem_capacity = mem_capacity.clamp(1, MAX_HASH_MB);
+            self.cache = Arc::new(Cache::new(mem_capacity));
+        }
+        if name.eq_ignore_ascii_case("Threads") {
+            let Some(Ok(thread_count)) = value.as_ref().map(|v| v.parse::<usize>()) else {
+                println!("info string malformed Threads value");
+                return;
+            };
+            self.thread_count = thread_count.clamp(1, MAX_THREADS);
+        }
+        #[cfg(feature = "nnue")]
+        if name.eq_ignore_ascii_case("EvalFile") {
+            let Some(path) = &value else { return };
+            self.halt_search();
+            match load_network(path) {
+                Ok(()) => {
+                    refresh_accumulator(&mut self.gstate);
+                    // The cached scores were computed by the previous evaluator.
+                    self.cache.clear();
+                },
+                Err(err) => println!("info string failed to load network {}: {:?}", path, err)
+            }
+        }
+        #[cfg(feature = "nnue")]
+        if name.eq_ignore_ascii_case("UseNNUE") {
+            self.halt_search();
+            if set_nnue_enabled(value.is_some_and(|v| v.eq_ignore_ascii_case("true"))) {
+                self.cache.clear();
+            }
+        }
+    }
+
+    fn handle_position(&mut self, tokens: &[&str]) {
+        let moves_pos = tokens.iter().position(|t| *t == "moves");
+        let mut gstate = match tokens.first() {
+            Some(&"startpos") => std_chess_position(self.hash_ch.clone()),
+            Some(&"fen") => {
+                let fen = tokens[1..moves_pos.unwrap_or(tokens.len())].join(" ");
+                match ChessGame::from_fen(&fen, self.hash_ch.clone()) {
+                    Ok(gstate) => gstate,
+                    Err(err) => {
+                        println!("info string rejected FEN {}: {:?}", fen, err);
+                        return;
+                    }
+                }
+            },
+            _ => {
+                println!("info string malformed position command");
+                return;
+            }
+        };
+        if let Some(moves_pos) = moves_pos {
+            for token in &tokens[moves_pos + 1..] {
+                match parse_lan(&mut gstate, token) {
+                    Ok(mov) => make_move(&mut gstate, mov),
+                    Err(err) => {
+                        println!("info string rejected move {}: {:?}", token, err);
+                        return;
+                    }
+                }
+            }
+        }
+        self.gstate = gstate;
+    }
+
+    fn handle_go(&mut self, tokens: &[&str]) {
+        self.halt_search();
+        if let ["perft", depth] = tokens {
+            match depth.parse::<u8>() {
+                Ok(depth) if depth > 0 => print_divide(&mut self.gstate, depth),
+                _ => println!("info string malformed perft depth"),
+            }
+            return;
+        }
+        let params = parse_go_params(tokens);
+        if count_legal_moves(&mut self.gstate) == 0 {
+            println!("bestmove 0000");
+            return;
+        }
+        let time_manager = calc_time_manager(&params, self.gstate.active_player());
+        let deadline = calc_deadline(&params, time_manager.as_ref())
+            .unwrap_or(Instant::now() + Duration::from_secs(60 * 60 * 24));
+        self.infinite = params.infinite;
+
+        let search_begin = Instant::now();
+        let mover = self.gstate.active_player();
+        self.mover = mover;
+        let id = self.next_search_id;
+        self.next_search_id += 1;
+        self.search = Some(spawn_search(BackgroundSearchParams { id,
+            gstate: self.gstate.clone(), deadline, max_depth: params.depth.unwrap_or(u8::MAX),
+            cache: self.cache.clone(), thread_count: self.thread_count, time_manager,
+            events: self.events.clone() },
+            move |result| report_iteration(result, mover, search_begin)));
+    }
+
+    fn handle_search_complete(&mut self, id: u64, result: IterDeepSearchResult) {
+        if self.search.as_ref().map(|s| s.id) != Some(id) { return; }
+        self.search.take().unwrap().halt();
+        if self.infinite { self.withheld = Some(result); }
+        else { self.report_bestmove(result); }
+    }
+
+    fn report_bestmove(&self, result: IterDeepSearchResult) {
+        println!("bestmove {}", format_lan(result.bestmove, self.mover));
+    }
+
+    /// Cancels the ongoing search (if any) without reporting a best move.
+    fn halt_search(&mut self) {
+        if let Some(search) = self.search.take() { search.halt(); }
+        self.infinite = false;
+        self.withheld = None;
+    }
+}
+
+fn report_iteration(result: &IterDeepSearchResult, mover: Color, search_begin: Instant) {
+    let score = match result.mate_in {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", to_centipawns(result.score))
+    };
+    println!("info depth {} score {} nodes {} time {} hashfull {} pv {}",
+        result.depth_achieved, score, result.node_count, search_begin.elapsed().as_millis(),
+        result.hashfull, format_lan_line(&result.pv, mover));
+}
+
+// # `go`
+
+#[derive(Default)]
+struct GoParams {
+    wtime: Option<Duration>,
+    btime: Option<Duration>,
+    winc: Option<Duration>,
+    binc: Option<Duration>,
+    movestogo: Option<u32>,
+    movetime: Option<Duration>,
+    depth: Option<u8>,
+    infinite: bool
+}
+
+fn parse_go_params(tokens: &[&str]) -> GoParams {
+    let mut params = GoParams::default();
+    let mut i = 0;
+    while i < tokens.len() {
+        let arg = tokens.get(i + 1).copied().unwrap_or("");
+        // Some controllers report a negative clock once time has been overdrawn.
+        let millis = arg.parse::<i64>().ok()
+            .map(|ms| Duration::from_millis(ms.max(0) as u64));
+        match tokens[i] {
+            "wtime" => params.wtime = millis,
+            "btime" => params.btime = millis,
+            "winc" => params.winc = millis,
+            "binc" => params.binc = millis,
+            "movestogo" => params.movestogo = arg.parse().ok(),
+            "movetime" => params.movetime = millis,
+            "depth" => params.depth = arg.parse().ok(),
+            "infinite" => { params.infinite = true; i += 1; continue; },
+            _ => { i += 1; continue; }
+        }
+        i += 2;
+    }
+    return params;
+}
+
+/// Constructs the [`TimeManager`] for a search timed by the mover's clock. Returns
+/// `None` when the controller did not report the clock, or dictated the think time.
+fn calc_time_manager(params: &GoParams, mover: Color) -> Option<TimeManager> {
+    if params.infinite | params.movetime.is_some() { return None; }
+    let (remaining, increment) = match mover {
+        Color::White => (params.wtime?, params.winc.unwrap_or_default()),
+        Color::Black => (params.btime?, params.binc.unwrap_or_default()),
+    };
+    let clock = Clock { remaining, increment, moves_to_go: params.movestogo };
+    return Some(TimeManager::new(calc_time_limits(&clock)));
+}
+
+/// Computes the instant by which the search must conclude. Returns `None` when the
+/// search is not time-limited, in which case it is bounded only by depth or `stop`.
+fn calc_deadline(params: &GoParams, time_manager: Option<&TimeManager>) -> Option<Instant> {
+    if params.infinite { return None; }
+    if let Some(movetime) = params.movetime { return Some(Instant::now() + movetime); }
+    return time_manager.map(TimeManager::hard_deadline);
+}
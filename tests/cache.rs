@@ -0,0 +1,154 @@
+use chess_solver_3::cache::{Cache, CacheValue, HashChars, ScoreBound};
+use chess_solver_3::eval::{MAX_SCORE, MIN_SCORE};
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::grid::Side;
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::make_move;
+use chess_solver_3::mov::{AnyMove, EncodedMove};
+use chess_solver_3::movegen::dispatch::movegen_legal;
+use chess_solver_3::movegen::types::GeneratedMove;
+use chess_solver_3::stdinit::std_chess_position;
+
+/// Lists the positions reachable in one move from the starting position.
+fn successors() -> Vec<ChessGame> {
+    let mut state = std_chess_position(HashChars::new([0; 32]));
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(&mut state, &mut moves);
+    return moves.iter().map(|genmove| {
+        let mut successor = state.clone();
+        make_move(&mut successor, genmove.mov);
+        return successor;
+    }).collect();
+}
+
+#[test]
+fn test_cache_bounds_respect_window() {
+    let mut state = std_chess_position(HashChars::new([0; 32]));
+    let cache = Cache::new(1);
+    let e2e4 = EncodedMove::encode(parse_lan(&mut state, "e2e4").unwrap());
+    let d2d4 = EncodedMove::encode(parse_lan(&mut state, "d2d4").unwrap());
+    let value = CacheValue { bestmov: e2e4, score: 3 };
+
+    // A lower bound is only conclusive when it causes a beta cutoff.
+    cache.update(&state, 4, 0, ScoreBound::Lower, value);
+    assert_eq!(cache.lookup_score(&state, 4, 0, 0, 2), Some(3));
+    assert_eq!(cache.lookup_score(&state, 4, 0, 0, 5), None);
+    // An entry from a shallower search is never conclusive.
+    assert_eq!(cache.lookup_score(&state, 5, 0, 0, 2), None);
+
+    // An upper bound is only conclusive when it is at most alpha.
+    cache.update(&state, 4, 0, ScoreBound::Upper, value);
+    assert_eq!(cache.lookup_score(&state, 4, 0, 3, 9), Some(3));
+    assert_eq!(cache.lookup_score(&state, 4, 0, 2, 9), None);
+
+    // An exact score is always conclusive and is not replaced by a bound of equal depth.
+    cache.update(&state, 4, 0, ScoreBound::Exact, value);
+    cache.update(&state, 4, 0, ScoreBound::Upper, CacheValue { bestmov: d2d4, score: -7 });
+    assert_eq!(cache.lookup_score(&state, 4, 0, -9, 9), Some(3));
+    // But it is replaced by a deeper search.
+    cache.update(&state, 5, 0, ScoreBound::Upper, CacheValue { bestmov: d2d4, score: -7 });
+    assert_eq!(cache.lookup_score(&state, 4, 0, -9, 9), None);
+    assert_eq!(cache.lookup_any(&state).unwrap().bestmov, d2d4);
+}
+
+#[test]
+fn test_cache_bucket_replacement_prefers_stale_entries() {
+    // A cache of the minimum size consists of a single bucket, so every position collides.
+    let cache = Cache::new(0);
+    let positions = successors();
+    let value = CacheValue { bestmov: EncodedMove::encode(AnyMove::Castle(Side::Kingside)), score: 0 };
+
+    cache.new_generation();
+    for state in &positions[0..4] { cache.update(state, 8, 0, ScoreBound::Exact, value); }
+    assert!(positions[0..4].iter().all(|state| cache.lookup_any(state).is_some()));
+
+    // The bucket is full, so the shallowest entry of the current generation is replaced.
+    cache.update(&positions[0], 9, 0, ScoreBound::Exact, value);
+    cache.update(&positions[4], 2, 0, ScoreBound::Exact, value);
+    assert!(cache.lookup_any(&positions[4]).is_some());
+    assert_eq!(positions[0..4].iter().filter(|s| cache.lookup_any(s).is_some()).count(), 3);
+    assert!(cache.lookup_any(&positions[0]).is_some());
+
+    // Once the deep entries have aged, they give way to shallow entries of the new search.
+    cache.new_generation();
+    cache.new_generation();
+    cache.new_generation();
+    for state in &positions[5..9] { cache.update(state, 1, 0, ScoreBound::Exact, value); }
+    assert!(positions[5..9].iter().all(|state| cache.lookup_any(state).is_some()));
+    assert!(positions[0..5].iter().all(|state| cache.lookup_any(state).is_none()));
+}
+
+#[test]
+fn test_cache_hashfull_counts_current_generation() {
+    let cache = Cache::new(0);
+    let positions = successors();
+    let value = CacheValue { bestmov: EncodedMove::encode(AnyMove::Castle(Side::Kingside)), score: 0 };
+    assert_eq!(cache.hashfull(), 0);
+    cache.update(&positions[0], 1, 0, ScoreBound::Exact, value);
+    cache.update(&positions[1], 1, 0, ScoreBound::Exact, value);
+    assert_eq!(cache.hashfull(), 500);
+    cache.new_generation();
+    assert_eq!(cache.hashfull(), 0);
+}
+
+#[test]
+fn test_encoded_move_roundtrip() {
+    let fen = "r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1";
+    let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(&mut state, &mut moves);
+    let encoded: Vec<EncodedMove> = moves.iter()
+        .map(|genmove| EncodedMove::encode(genmove.mov))
+        .collect();
+    for (genmove, encoding) in moves.iter().zip(encoded.iter()) {
+        assert_eq!(encoding.decode(), genmove.mov);
+        assert_eq!(encoded.iter().filter(|other| *other == encoding).count(), 1);
+    }
+    // Both castles, and every promotion (with and without capture) are represented.
+    assert!(moves.iter().any(|genmove| genmove.mov == AnyMove::Castle(Side::Queenside)));
+    assert_eq!(moves.iter().filter(|genmove| matches!(genmove.mov,
+        AnyMove::Piece(pmove) if pmove.promote.is_some())).count(), 8);
+}
+
+#[test]
+fn test_cache_mate_scores_are_relative_to_position() {
+    let state = std_chess_position(HashChars::new([0; 32]));
+    let cache = Cache::new(1);
+    let bestmov = EncodedMove::encode(AnyMove::Castle(Side::Kingside));
+    // A mate delivered 7 plies from the root, found at a position 3 plies from the root,
+    // is delivered 4 plies from the position, and so 9 plies from a root 5 plies away.
+    cache.update(&state, 4, 3, ScoreBound::Exact, CacheValue { bestmov, score: MAX_SCORE - 7 });
+    assert_eq!(cache.lookup_score(&state, 4, 5, -9, 9), Some(MAX_SCORE - 9));
+    assert_eq!(cache.lookup_any(&state).unwrap().score, MAX_SCORE - 4);
+    cache.update(&state, 5, 3, ScoreBound::Exact, CacheValue { bestmov, score: MIN_SCORE + 7 });
+    assert_eq!(cache.lookup_score(&state, 4, 1, -9, 9), Some(MIN_SCORE + 5));
+    // Material scores are unaffected.
+    cache.update(&state, 6, 3, ScoreBound::Exact, CacheValue { bestmov, score: -5 });
+    assert_eq!(cache.lookup_score(&state, 4, 1, -9, 9), Some(-5));
+}
+
+#[test]
+fn test_cache_concurrent_updates_are_never_torn() {
+    // Every position collides in a single bucket, which several threads update at once.
+    let cache = Cache::new(0);
+    let positions = successors();
+    let score_of = |state: &ChessGame| (state.hash.value() % 1000) as i16;
+    std::thread::scope(|scope| {
+        for offset in 0..4 {
+            let (cache, positions) = (&cache, &positions);
+            scope.spawn(move || {
+                for round in 0..2000 {
+                    let state = &positions[(round + offset) % positions.len()];
+                    let bestmov = EncodedMove::encode(AnyMove::Castle(Side::Kingside));
+                    cache.update(state, (round % 16) as u8, 0, ScoreBound::Exact,
+                        CacheValue { bestmov, score: score_of(state) });
+                    for other in positions {
+                        if let Some(value) = cache.lookup_any(other) {
+                            assert_eq!(value.score, score_of(other));
+                        }
+                    }
+                }
+            });
+        }
+    });
+}
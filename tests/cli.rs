@@ -0,0 +1,12 @@
+use chess_solver_3::cli::format_integer;
+
+#[test]
+fn test_format_integer() {
+    assert_eq!(format_integer(0), "0");
+    assert_eq!(format_integer(999), "999");
+    assert_eq!(format_integer(1000), "1,000");
+    // A number whose length is a multiple of three has no leading comma.
+    assert_eq!(format_integer(123456), "123,456");
+    assert_eq!(format_integer(338043u64), "338,043");
+    assert_eq!(format_integer(1234567), "1,234,567");
+}
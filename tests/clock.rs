@@ -0,0 +1,91 @@
+use chess_solver_3::clock::{Clock, TimeLimits, TimeManager, calc_time_limits};
+use chess_solver_3::grid::Side;
+use chess_solver_3::mov::AnyMove;
+use chess_solver_3::search::IterDeepSearchResult;
+use std::time::Duration;
+
+#[test]
+fn test_time_limits_respect_clock() {
+    let secs = Duration::from_secs;
+    let sudden_death = calc_time_limits(&Clock { remaining: secs(60), increment: secs(0),
+        moves_to_go: None });
+    assert!(sudden_death.soft < sudden_death.hard);
+    assert!(sudden_death.hard <= secs(30));
+
+    // The increment is regained after the move, so most of it may be spent.
+    let incremental = calc_time_limits(&Clock { remaining: secs(60), increment: secs(2),
+        moves_to_go: None });
+    assert!(incremental.soft > sudden_death.soft + secs(1));
+
+    // On the last move before the time control, all but the overhead may be spent.
+    let last_move = calc_time_limits(&Clock { remaining: secs(10), increment: secs(0),
+        moves_to_go: Some(1) });
+    assert!(last_move.hard < secs(10));
+    assert!(last_move.hard > secs(9));
+
+    // Neither limit exceeds the remaining time, however large the increment.
+    let low_time = calc_time_limits(&Clock { remaining: Duration::from_millis(20),
+        increment: secs(5), moves_to_go: None });
+    assert_eq!(low_time, TimeLimits::fixed(Duration::ZERO));
+}
+
+fn iteration(bestmove: AnyMove, score: i16) -> IterDeepSearchResult {
+    return IterDeepSearchResult { bestmove, pv: vec![bestmove], score, mate_in: None,
+        depth_achieved: 1, node_count: 0, hashfull: 0 };
+}
+
+/// Asks a copy of the time manager, so that the original is left as it was.
+fn deepens_at(mut time_manager: TimeManager, elapsed: Duration, result: &IterDeepSearchResult)
+    -> bool
+{
+    return time_manager.should_deepen_at(elapsed, result);
+}
+
+#[test]
+fn test_time_manager_adapts_to_search() {
+    let ms = Duration::from_millis;
+    let limits = TimeLimits { soft: ms(400), hard: Duration::from_secs(10) };
+    let (kingside, queenside) = (AnyMove::Castle(Side::Kingside), AnyMove::Castle(Side::Queenside));
+
+    let mut stable = TimeManager::new(limits);
+    let mut unstable = TimeManager::new(limits);
+    let mut failing_low = TimeManager::new(limits);
+    for index in 0..4 {
+        let elapsed = ms(index + 1);
+        assert!(stable.should_deepen_at(elapsed, &iteration(kingside, 0)));
+        let bestmove = if index % 2 == 0 { kingside } else { queenside };
+        assert!(unstable.should_deepen_at(elapsed, &iteration(bestmove, 0)));
+        assert!(failing_low.should_deepen_at(elapsed, &iteration(kingside, 0)));
+    }
+
+    // A best move which survived five iterations halves the soft limit...
+    assert!(deepens_at(stable, ms(199), &iteration(kingside, 0)));
+    assert!(!deepens_at(stable, ms(200), &iteration(kingside, 0)));
+    // ...while a best move which just changed extends it by half...
+    assert!(deepens_at(unstable, ms(599), &iteration(kingside, 0)));
+    assert!(!deepens_at(unstable, ms(600), &iteration(kingside, 0)));
+    // ...and a fail-low doubles it.
+    assert!(deepens_at(failing_low, ms(399), &iteration(kingside, -100)));
+    assert!(!deepens_at(failing_low, ms(400), &iteration(kingside, -100)));
+}
+
+#[test]
+fn test_time_manager_never_starts_unfinishable_iteration() {
+    let ms = Duration::from_millis;
+    let limits = TimeLimits { soft: Duration::from_secs(10), hard: ms(300) };
+    let result = iteration(AnyMove::Castle(Side::Kingside), 0);
+
+    // Without a previous iteration to compare against, the next iteration is expected
+    // to take four times as long as the first.
+    let first = TimeManager::new(limits);
+    assert!(deepens_at(first, ms(55), &result));
+    assert!(!deepens_at(first, ms(65), &result));
+
+    // Otherwise the growth between the last two iterations is extrapolated: after
+    // iterations of 10ms and 20ms, a 60ms iteration projects a 180ms iteration next.
+    let mut growing = TimeManager::new(limits);
+    assert!(growing.should_deepen_at(ms(10), &result));
+    assert!(growing.should_deepen_at(ms(30), &result));
+    assert!(deepens_at(growing, ms(90), &result));
+    assert!(!deepens_at(growing, ms(110), &result));
+}
@@ -0,0 +1,36 @@
+//! Fixtures shared by the integration tests. Not every test uses every fixture.
+#![allow(dead_code)]
+
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::makemove::{make_move, unmake_move};
+use chess_solver_3::movegen::dispatch::movegen_legal;
+use chess_solver_3::movegen::types::GeneratedMove;
+
+pub fn position(fen: &str) -> ChessGame {
+    return ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+}
+
+/// Positions from which castling, enpassant and promotions (with and without
+/// capture) are all reachable within two plies.
+pub const SPECIAL_MOVE_FENS: [&str; 3] = [
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"
+];
+
+/// Plays every legal move to the given depth and calls `verify` on every position
+/// reached, and again after each move is taken back. `verify` should assert that some
+/// incrementally maintained state agrees with that state computed from scratch.
+pub fn verify_incremental(state: &mut ChessGame, depth: u8, verify: &impl Fn(&ChessGame)) {
+    verify(state);
+    if depth == 0 { return; }
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut moves);
+    for genmove in moves {
+        make_move(state, genmove.mov);
+        verify_incremental(state, depth - 1, verify);
+        unmake_move(state);
+        verify(state);
+    }
+}
@@ -0,0 +1,80 @@
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::fen::{ParseFenErr, STARTING_FEN};
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::grid::{File, Rank, StandardCoordinate};
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::{make_move, unmake_move};
+use chess_solver_3::persistence::apply_gstr;
+use chess_solver_3::piece::Color;
+use chess_solver_3::stdinit::std_chess_position;
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+#[test]
+fn test_fen_startpos_matches_std_position() {
+    let hash_ch = HashChars::new([7; 32]);
+    let from_fen = ChessGame::from_fen(STARTING_FEN, hash_ch.clone()).unwrap();
+    let std = std_chess_position(hash_ch);
+    assert!(from_fen == std);
+    assert_eq!(std.to_fen(), STARTING_FEN);
+}
+
+#[test]
+fn test_fen_roundtrip() {
+    for fen in [KIWIPETE,
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"]
+    {
+        let game = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+        assert_eq!(game.to_fen(), fen);
+    }
+}
+
+#[test]
+fn test_fen_tracks_played_moves() {
+    let mut game = ChessGame::from_fen(STARTING_FEN, HashChars::new([0; 32])).unwrap();
+    apply_gstr(&mut game, "E2:E4; A7:A6; E4:E5; D7:D5;").unwrap();
+    assert_eq!(game.to_fen(), "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3");
+}
+
+#[test]
+fn test_fen_hash_matches_played_position() {
+    let hash_ch = HashChars::new([3; 32]);
+    let mut game = std_chess_position(hash_ch.clone());
+    apply_gstr(&mut game, "E2:E4; A7:A6; E4:E5; D7:D5;").unwrap();
+    let from_fen = ChessGame::from_fen(&game.to_fen(), hash_ch).unwrap();
+    assert_eq!(from_fen.hash.value(), game.hash.value());
+}
+
+#[test]
+fn test_fen_enpassant_without_history() {
+    let fen = "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3";
+    let mut game = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let before = game.clone();
+    let mov = parse_lan(&mut game, "e5f6").unwrap();
+    make_move(&mut game, mov);
+    let captured_sq = StandardCoordinate::new(Rank::from_index(4), File::F);
+    assert!(game.p_lut.get(captured_sq).is_none());
+    assert_eq!(game.active_player(), Color::Black);
+    unmake_move(&mut game);
+    assert!(game == before);
+}
+
+#[test]
+fn test_fen_rejects_malformed() {
+    let hash_ch = HashChars::new([0; 32]);
+    assert!(matches!(ChessGame::from_fen("8/8/8/8/8/8/8/8 w - -", hash_ch.clone()),
+        Err(ParseFenErr::BadKingCount(Color::White))));
+    assert!(matches!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3", hash_ch.clone()),
+        Err(ParseFenErr::BadFieldCount)));
+    assert!(matches!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3 w KQ -", hash_ch.clone()),
+        Err(ParseFenErr::InconsistentCastlingRights)));
+    assert!(matches!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K2 w - -", hash_ch.clone()),
+        Err(ParseFenErr::BadRankLength(_))));
+    assert!(matches!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3 w - e3", hash_ch.clone()),
+        Err(ParseFenErr::InconsistentEnpassant)));
+    assert!(matches!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 32769", hash_ch.clone()),
+        Err(ParseFenErr::BadFullmoveNumber(32769))));
+    assert!(ChessGame::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 32768", hash_ch).is_ok());
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use chess_solver_3::eval_params::EVAL_PARAMS;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::king_safety::calc_king_safety;
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::make_move;
+use chess_solver_3::pos_eval::PositionalScore;
+use common::position;
+
+fn safety(state: &ChessGame) -> PositionalScore {
+    return calc_king_safety(&state.bbs, &state.has_castled, &EVAL_PARAMS);
+}
+
+fn safety_mg(fen: &str) -> i16 { safety(&position(fen)).mg }
+
+#[test]
+fn test_king_safety_pawn_cover() {
+    assert_eq!(safety_mg("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+    // An intact pawn shield is better than one which has been pushed or traded off.
+    let intact = safety_mg("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1");
+    assert!(intact > safety_mg("6k1/5ppp/8/8/8/6PP/5P2/6K1 w - - 0 1"));
+    assert!(intact > safety_mg("6k1/5ppp/8/8/8/8/5P1P/6K1 w - - 0 1"));
+    // Enemy pawns storming toward the king are dangerous.
+    assert!(safety_mg("6k1/5p2/8/8/8/6pp/5PPP/6K1 w - - 0 1")
+        < safety_mg("6k1/5p2/8/8/6pp/8/5PPP/6K1 w - - 0 1"));
+}
+
+#[test]
+fn test_king_safety_zone_attacks() {
+    // The queen and knight coordinate against the king.
+    let attacked = safety_mg("r4rk1/5ppp/8/6NQ/8/8/5PPP/R5K1 w - - 0 1");
+    let distant = safety_mg("r4rk1/5ppp/8/8/8/8/5PPP/RN1Q2K1 w - - 0 1");
+    assert!(attacked > distant);
+    // A lone attacker is not yet considered a threat.
+    assert_eq!(safety_mg("r4rk1/5ppp/8/7Q/8/8/5PPP/RN4K1 w - - 0 1"), distant);
+}
+
+#[test]
+fn test_king_safety_rewards_castling() {
+    let mut state = position("r3k2r/pppppppp/8/8/8/8/PPPPPPPP/R3K2R w KQkq - 0 1");
+    let before = safety(&state).mg;
+    let castle = parse_lan(&mut state, "e1g1").unwrap();
+    make_move(&mut state, castle);
+    assert!(safety(&state).mg > before);
+}
+
+#[test]
+fn test_king_safety_attack_penalty_is_bounded() {
+    // However many pieces swarm the king, the penalty stops growing at its limit.
+    let swarmed = safety_mg("7K/1QQ1QQ2/Q6Q/2Q1k3/Q6Q/1QQ1QQ2/8/8 b - - 0 1");
+    let outnumbered = safety_mg("7K/8/8/2Q1k3/Q6Q/1QQ1QQ2/8/8 b - - 0 1");
+    assert_eq!(swarmed, outnumbered);
+}
@@ -0,0 +1,36 @@
+use chess_solver_3::grid::{File, Rank, Side, StandardCoordinate};
+use chess_solver_3::lan::{format_lan, parse_lan, ParseLanErr};
+use chess_solver_3::mov::{AnyMove, PieceMove};
+use chess_solver_3::persistence::apply_gstr;
+use chess_solver_3::piece::{Color, Species};
+use chess_solver_3::stdinit::new_std_chess_position;
+
+#[test]
+fn test_lan_castle_roundtrip() {
+    let mut game = new_std_chess_position();
+    apply_gstr(&mut game, "E2:E4; E7:E5; F1:E2; F8:E7; G1:F3; G8:F6;").unwrap();
+    let mov = parse_lan(&mut game, "e1g1").unwrap();
+    assert_eq!(mov, AnyMove::Castle(Side::Kingside));
+    assert_eq!(format_lan(mov, Color::White), "e1g1");
+    assert_eq!(format_lan(AnyMove::Castle(Side::Queenside), Color::Black), "e8c8");
+}
+
+#[test]
+fn test_lan_promotion_roundtrip() {
+    let mut game = new_std_chess_position();
+    apply_gstr(&mut game, "B2:B4; A7:A5; B4:A5; B7:B6; A5:B6; H7:H6; B6:C7; H6:H5")
+        .unwrap();
+    let mov = parse_lan(&mut game, "c7b8n").unwrap();
+    let origin = StandardCoordinate::new(Rank::from_index(6), File::C);
+    let destin = StandardCoordinate::new(Rank::from_index(7), File::B);
+    let expected = AnyMove::Piece(PieceMove { origin, destin, promote: Some(Species::Knight) });
+    assert_eq!(mov, expected);
+    assert_eq!(format_lan(mov, Color::White), "c7b8n");
+}
+
+#[test]
+fn test_lan_rejects_illegal() {
+    let mut game = new_std_chess_position();
+    assert!(matches!(parse_lan(&mut game, "e2e5"), Err(ParseLanErr::IllegalMove(_))));
+    assert!(matches!(parse_lan(&mut game, "e2"), Err(ParseLanErr::BadLen)));
+}
@@ -4,12 +4,13 @@
 //! that the move was applied correctly however, and so this suite alone is not sufficient
 //! to verify the total correctness of `make_move` and `unmake_move`.
 
+use chess_solver_3::cache::HashChars;
 use chess_solver_3::gamestate::ChessGame;
 use chess_solver_3::grid::{File, Side, Rank, StandardCoordinate};
-use chess_solver_3::makemove::{make_move, unmake_move};
+use chess_solver_3::makemove::{make_move, make_null_move, unmake_move, unmake_null_move};
 use chess_solver_3::mov::{AnyMove, PieceMove};
 use chess_solver_3::persistence::apply_gstr;
-use chess_solver_3::stdinit::new_std_chess_position;
+use chess_solver_3::stdinit::{new_std_chess_position, std_chess_position};
 
 pub fn test_make_unmake(board: &mut ChessGame, mov: AnyMove) {
     let before_snapshot = board.clone();
@@ -82,4 +83,20 @@ pub fn test_make_unmake_pmove_black() {
     let origin_sq = StandardCoordinate::new(Rank::from_index(6), File::A);
     let destin_sq = StandardCoordinate::new(Rank::from_index(4), File::A);
     test_make_unmake(&mut board, AnyMove::Piece(PieceMove::new_basic(origin_sq, destin_sq)));
-}
\ No newline at end of file
+}
+
+#[test]
+pub fn test_make_unmake_null_move() {
+    let hash_ch = HashChars::new([0; 32]);
+    let mut board = std_chess_position(hash_ch.clone());
+    apply_gstr(&mut board, "E2:E4; A7:A6; E4:E5; D7:D5;").unwrap();
+    let before_snapshot = board.clone();
+    make_null_move(&mut board);
+    // Passing forfeits the opportunity to capture enpassant.
+    let expected_fen = "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR b KQkq - 1 3";
+    assert_eq!(board.to_fen(), expected_fen);
+    let expected = ChessGame::from_fen(expected_fen, hash_ch).unwrap();
+    assert_eq!(board.hash.value(), expected.hash.value());
+    unmake_null_move(&mut board);
+    assert!(before_snapshot == board);
+}
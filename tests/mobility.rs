@@ -0,0 +1,47 @@
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::eval_params::EVAL_PARAMS;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::mobility::calc_mobility;
+use chess_solver_3::pos_eval::PositionalScore;
+
+fn mobility(fen: &str) -> PositionalScore {
+    let state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    return calc_mobility(&state.bbs, &EVAL_PARAMS);
+}
+
+#[test]
+fn test_mobility_is_symmetric() {
+    assert_eq!(mobility("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").mg, 0);
+    assert_eq!(mobility("r1bqkb1r/pppp1ppp/2n2n2/4p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R w KQkq - 4 4"),
+        PositionalScore::default());
+}
+
+#[test]
+fn test_mobility_terms() {
+    // A centralized knight controls more squares than one on the rim.
+    assert!(mobility("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").mg
+        > mobility("4k3/8/8/8/7N/8/8/4K3 w - - 0 1").mg);
+    // Squares attacked by enemy pawns do not count toward mobility.
+    assert!(mobility("4k3/8/8/8/3N4/8/8/4K3 w - - 0 1").mg
+        > mobility("4k3/8/2p5/4p3/3N4/8/8/4K3 w - - 0 1").mg);
+    // The bishop pair.
+    assert!(mobility("4k3/8/8/8/8/8/8/2B1KB2 w - - 0 1").eg
+        > mobility("4k3/8/8/8/8/8/8/2N1KB2 w - - 0 1").eg);
+    // A rook on an open file is better than one behind its own pawn.
+    assert!(mobility("4k3/8/8/8/8/8/1P6/R3K3 w - - 0 1").mg
+        > mobility("4k3/8/8/8/8/8/P7/R3K3 w - - 0 1").mg);
+    // A rook on the seventh rank confining the enemy king.
+    assert!(mobility("6k1/R7/8/8/8/8/8/6K1 w - - 0 1").eg
+        > mobility("6k1/8/R7/8/8/8/8/6K1 w - - 0 1").eg);
+}
+
+#[test]
+fn test_knight_outpost_and_trapped_pieces() {
+    // The knight on d5 is supported by e4, and no black pawn can ever attack it,
+    // unless a black pawn remains on the e-file.
+    assert!(mobility("4k3/5p2/8/3N4/4P3/8/8/4K3 w - - 0 1").mg
+        > mobility("4k3/4p3/8/3N4/4P3/8/8/4K3 w - - 0 1").mg);
+    // A bishop shut in by the enemy pawns is trapped.
+    assert!(mobility("4k3/1p6/Bp6/8/8/8/8/4K3 w - - 0 1").mg
+        < mobility("4k3/1p6/1p6/8/8/8/8/B3K3 w - - 0 1").mg);
+}
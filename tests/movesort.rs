@@ -0,0 +1,39 @@
+use chess_solver_3::cache::{Cache, HashChars};
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::make_move;
+use chess_solver_3::misc::SegVec;
+use chess_solver_3::movesort::{MoveHistory, movegen_legal_sorted};
+use chess_solver_3::piece::Color;
+use chess_solver_3::stdinit::std_chess_position;
+use std::cell::RefCell;
+
+#[test]
+fn test_history_orders_cutoff_moves_first() {
+    let mut state = std_chess_position(HashChars::new([0; 32]));
+    let e2e4 = parse_lan(&mut state, "e2e4").unwrap();
+    make_move(&mut state, e2e4);
+    let g8f6 = parse_lan(&mut state, "g8f6").unwrap();
+    let b8c6 = parse_lan(&mut state, "b8c6").unwrap();
+    let cache = Cache::new(1);
+    let mut history = MoveHistory::new();
+    history.record_cutoff(&state, g8f6, 1, 4);
+    assert!(history.is_killer(g8f6, 1));
+    assert!(!history.is_killer(g8f6, 2));
+    assert_eq!(history.lookup_countermove(&state), Some(g8f6));
+    assert_eq!(history.lookup_butterfly(Color::Black, g8f6), 16);
+    assert_eq!(history.lookup_butterfly(Color::White, g8f6), 0);
+
+    let mut buffer = RefCell::default();
+    let mut moves = SegVec::new(&mut buffer);
+    movegen_legal_sorted(&mut state, &mut moves, &cache, &history, 1);
+    assert_eq!(moves.pop().unwrap().mov, g8f6);
+    drop(moves);
+
+    // The killer moves of other plies are not preferred, but the countermove still is,
+    // even though the history of the other move is greater.
+    history.record_cutoff(&state, b8c6, 1, 1);
+    assert!(history.is_killer(g8f6, 1) & history.is_killer(b8c6, 1));
+    let mut moves = SegVec::new(&mut buffer);
+    movegen_legal_sorted(&mut state, &mut moves, &cache, &history, 3);
+    assert_eq!(moves.pop().unwrap().mov, b8c6);
+}
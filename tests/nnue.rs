@@ -0,0 +1,80 @@
+#![cfg(feature = "nnue")]
+
+mod common;
+
+use chess_solver_3::nnue::{Accumulator, HIDDEN_LEN, INPUT_LEN, LoadNetworkErr, Network};
+use chess_solver_3::nnue::{format_network, install_network, loaded_network, nnue_eval};
+use chess_solver_3::nnue::parse_network;
+use chess_solver_3::piece::Color;
+use common::{SPECIAL_MOVE_FENS, position, verify_incremental};
+use std::sync::{Arc, Once};
+
+/// Generates an arbitrary but fixed network with small weights.
+fn sample_network() -> Network {
+    let mut seed: u32 = 0x2545F491;
+    let mut next = move |range: i16| {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        return (seed % (2 * range as u32 + 1)) as i16 - range;
+    };
+    let mut hidden_weights = vec![[0i16; HIDDEN_LEN]; INPUT_LEN];
+    for weights in hidden_weights.iter_mut() {
+        for weight in weights.iter_mut() { *weight = next(64); }
+    }
+    let mut hidden_bias = [0i16; HIDDEN_LEN];
+    for bias in hidden_bias.iter_mut() { *bias = next(64) + 64; }
+    let output_weights: Vec<i16> = (0..(2 * HIDDEN_LEN)).map(|_| next(64)).collect();
+    return Network { hidden_weights, hidden_bias, output_weights, output_bias: next(64) };
+}
+
+/// Installs the sample network, which must happen before any position is constructed
+/// for its accumulator to be maintained incrementally.
+fn network() -> Arc<Network> {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| install_network(sample_network()));
+    return loaded_network().unwrap();
+}
+
+#[test]
+fn test_accumulator_is_incremental() {
+    let network = network();
+    for fen in SPECIAL_MOVE_FENS {
+        let mut state = position(fen);
+        assert!(state.accumulator.network.is_some());
+        verify_incremental(&mut state, 2, &|state| {
+            assert!(state.accumulator == Accumulator::compute(&state.bbs, network.clone()));
+        });
+    }
+}
+
+#[test]
+fn test_nnue_eval_is_symmetric() {
+    network();
+    let initial = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert!(initial.accumulator.values[Color::White] == initial.accumulator.values[Color::Black]);
+
+    // Mirroring the board vertically and swapping colors yields the same evaluation.
+    let white = position("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+    let black = position("rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4");
+    assert!(nnue_eval(&white).is_some());
+    assert_eq!(nnue_eval(&white), nnue_eval(&black));
+}
+
+#[test]
+fn test_network_file_round_trip() {
+    let network = sample_network();
+    let bytes = format_network(&network);
+    assert_eq!(parse_network(&bytes).unwrap(), network);
+
+    let mut bad_magic = bytes.clone();
+    bad_magic[0] = b'X';
+    assert!(matches!(parse_network(&bad_magic), Err(LoadNetworkErr::BadMagic)));
+
+    let mut bad_hidden_len = bytes.clone();
+    bad_hidden_len[8] = bad_hidden_len[8].wrapping_add(1);
+    assert!(matches!(parse_network(&bad_hidden_len), Err(LoadNetworkErr::HiddenLenMismatch(_))));
+
+    let truncated = &bytes[..bytes.len() - 1];
+    assert!(matches!(parse_network(truncated), Err(LoadNetworkErr::WrongLength(_))));
+}
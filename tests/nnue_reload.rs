@@ -0,0 +1,33 @@
+//! Replaces the loaded network, which would disturb the tests in `nnue.rs` if they
+//! shared a process.
+#![cfg(feature = "nnue")]
+
+mod common;
+
+use chess_solver_3::nnue::{HIDDEN_LEN, INPUT_LEN, Network};
+use chess_solver_3::nnue::{install_network, loaded_network, refresh_accumulator};
+use chess_solver_3::piece::Color;
+use common::position;
+use std::sync::Arc;
+
+fn uniform_network(weight: i16) -> Network {
+    return Network { hidden_weights: vec![[weight; HIDDEN_LEN]; INPUT_LEN],
+        hidden_bias: [0; HIDDEN_LEN], output_weights: vec![weight; 2 * HIDDEN_LEN],
+        output_bias: 0 };
+}
+
+#[test]
+fn test_network_can_be_replaced() {
+    install_network(uniform_network(1));
+    let first = loaded_network().unwrap();
+    let mut state = position("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+    // Existing positions keep the network they were constructed with until refreshed.
+    install_network(uniform_network(2));
+    let second = loaded_network().unwrap();
+    assert!(!Arc::ptr_eq(&first, &second));
+    assert!(Arc::ptr_eq(state.accumulator.network.as_ref().unwrap(), &first));
+    refresh_accumulator(&mut state);
+    assert!(Arc::ptr_eq(state.accumulator.network.as_ref().unwrap(), &second));
+    assert_eq!(state.accumulator.values[Color::White], [4; HIDDEN_LEN]);
+}
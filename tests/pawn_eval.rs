@@ -0,0 +1,55 @@
+mod common;
+
+use chess_solver_3::eval_params::EVAL_PARAMS;
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::{make_move, unmake_move};
+use chess_solver_3::pawn_eval::{calc_pawn_structure, PawnCache};
+use common::position;
+
+fn pawn_mg(fen: &str) -> i16 { calc_pawn_structure(&position(fen).bbs, &EVAL_PARAMS).mg }
+fn pawn_eg(fen: &str) -> i16 { calc_pawn_structure(&position(fen).bbs, &EVAL_PARAMS).eg }
+
+#[test]
+fn test_pawn_structure_terms() {
+    // Symmetric pawn structures are balanced.
+    assert_eq!(calc_pawn_structure(&position(
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").bbs, &EVAL_PARAMS).mg, 0);
+    // Doubled and isolated pawns are worse than a healthy chain.
+    assert!(pawn_mg("4k3/8/8/8/8/2P5/2P5/4K3 w - - 0 1")
+        < pawn_mg("4k3/8/8/8/8/2P5/1P6/4K3 w - - 0 1"));
+    // A passed pawn is worth more the further it has advanced, especially in the endgame.
+    assert!(pawn_eg("4k3/8/4P3/8/8/8/8/4K3 w - - 0 1")
+        > pawn_eg("4k3/8/8/8/4P3/8/8/4K3 w - - 0 1"));
+    // A pawn is no longer passed once an enemy pawn on an adjacent file stands in front.
+    assert!(pawn_eg("4k3/p7/4P3/8/8/8/8/4K3 w - - 0 1")
+        > pawn_eg("4k3/3p4/4P3/8/8/8/8/4K3 w - - 0 1"));
+    // The d3 pawn is backward once d4 is guarded by e5, since c4 has already advanced.
+    assert!(pawn_mg("4k3/8/4p3/8/2P5/3P4/8/4K3 w - - 0 1")
+        > pawn_mg("4k3/8/8/4p3/2P5/3P4/8/4K3 w - - 0 1"));
+}
+
+#[test]
+fn test_pawn_hash_tracks_pawns_only() {
+    let mut state = position("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+    let initial = state.hash.pawn_value();
+
+    let knight_move = parse_lan(&mut state, "f3e5").unwrap();
+    make_move(&mut state, knight_move);
+    assert_ne!(state.hash.pawn_value(), initial, "capturing a pawn changes the pawn hash");
+    unmake_move(&mut state);
+    assert_eq!(state.hash.pawn_value(), initial);
+
+    let bishop_move = parse_lan(&mut state, "f1c4").unwrap();
+    make_move(&mut state, bishop_move);
+    assert_eq!(state.hash.pawn_value(), initial, "piece moves leave the pawn hash alone");
+    unmake_move(&mut state);
+
+    let pawn_move = parse_lan(&mut state, "d2d4").unwrap();
+    make_move(&mut state, pawn_move);
+    assert_ne!(state.hash.pawn_value(), initial);
+    let mut cache = PawnCache::new();
+    assert_eq!(cache.lookup(&state), calc_pawn_structure(&state.bbs, &EVAL_PARAMS));
+    assert_eq!(cache.lookup(&state), calc_pawn_structure(&state.bbs, &EVAL_PARAMS));
+    unmake_move(&mut state);
+    assert_eq!(cache.lookup(&state), calc_pawn_structure(&state.bbs, &EVAL_PARAMS));
+}
@@ -0,0 +1,68 @@
+//! Verifies move generation by comparing perft node counts against the published
+//! values for a set of well-known positions, each chosen to exercise a particular
+//! class of edge cases (castling, enpassant, promotion, pins and checks).
+
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::fen::STARTING_FEN;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::lan::format_lan;
+use chess_solver_3::perft::{divide, perft};
+
+fn test_perft(fen: &str, expected: &[u64]) {
+    let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let before = state.clone();
+    for (i, expected_count) in expected.iter().enumerate() {
+        let depth = u8::try_from(i + 1).unwrap();
+        assert_eq!(perft(&mut state, depth), *expected_count, "{} at depth {}", fen, depth);
+    }
+    assert!(state == before);
+}
+
+#[test]
+fn test_perft_startpos() {
+    test_perft(STARTING_FEN, &[20, 400, 8902, 197281]);
+}
+
+#[test]
+fn test_perft_kiwipete() {
+    test_perft("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97862]);
+}
+
+#[test]
+fn test_perft_enpassant_pins() {
+    test_perft("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1", &[14, 191, 2812, 43238]);
+}
+
+#[test]
+fn test_perft_promotions() {
+    test_perft("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467]);
+    // The mirrored position must yield identical counts.
+    test_perft("r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9467]);
+}
+
+#[test]
+fn test_perft_discovered_checks() {
+    test_perft("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62379]);
+}
+
+#[test]
+fn test_perft_middlegame() {
+    test_perft("r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89890]);
+}
+
+#[test]
+fn test_divide() {
+    let mut state = ChessGame::from_fen(STARTING_FEN, HashChars::new([0; 32])).unwrap();
+    let subtree_counts = divide(&mut state, 3);
+    assert_eq!(subtree_counts.len(), 20);
+    assert_eq!(subtree_counts.iter().map(|(_, count)| count).sum::<u64>(), 8902);
+    let (_, e2e4_count) = subtree_counts.iter()
+        .find(|(mov, _)| format_lan(*mov, state.active_player()) == "e2e4")
+        .unwrap();
+    assert_eq!(*e2e4_count, 600);
+}
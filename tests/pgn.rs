@@ -0,0 +1,125 @@
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::gamestate::{ChessGame, GameResult};
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::make_move;
+use chess_solver_3::pgn::{PgnGame, ReadPgnErr, read_pgn, write_pgn};
+use chess_solver_3::piece::Color;
+
+const ANNOTATED_GAME: &str = r#"[Event "F/S Return Match"]
+[Site "Belgrade, Serbia JUG"]
+[Date "1992.11.04"]
+[Round "29"]
+[White "Fischer, Robert J."]
+[Black "Spassky, Boris V."]
+[Result "1/2-1/2"]
+[ECO "C95"]
+
+{Opening comment} 1. e4 e5 2. Nf3 Nc6 3. Bb5 {This opening is called the Ruy Lopez.}
+3... a6 4. Ba4 Nf6 5. O-O Be7 6. Re1 b5 7. Bb3 d6 8. c3 O-O 9. h3 Nb8 10. d4 Nbd7
+11. c4 c6 12. cxb5 axb5 13. Nc3 Bb7 14. Bg5 b4 15. Nb1 h6 16. Bh4 c5 17. dxe5
+Nxe4 18. Bxe7 Qxe7 19. exd6 Qf6 20. Nbd2 Nxd6 21. Nc4 Nxc4 22. Bxc4 Nb6
+23. Ne5 Rae8 24. Bxf7+ Rxf7 25. Nxf7 Rxe1+ 26. Qxe1 Kxf7 27. Qe3 Qg5 28. Qxg5
+hxg5 29. b3 Ke6 30. a3 Kd6 31. axb4 cxb4 32. Ra5 Nd5 33. f3 Bc8 34. Kf2 Bf5
+35. Ra7 g6 36. Ra6+ Kc5 37. Ke1 Nf4 38. g3 Nxh3 39. Kd2 Kb5 40. Rd6 Kc5 41. Ra6
+Nf2 42. g4 Bd3 43. Re6 1/2-1/2
+"#;
+
+fn roundtrip(game: &PgnGame) -> PgnGame {
+    let mut exported: Vec<u8> = Vec::new();
+    write_pgn(&mut exported, game).unwrap();
+    let mut games = read_pgn(&String::from_utf8(exported).unwrap()).unwrap();
+    assert_eq!(games.len(), 1);
+    return games.pop().unwrap();
+}
+
+#[test]
+fn test_pgn_read_annotated_game() {
+    let games = read_pgn(ANNOTATED_GAME).unwrap();
+    assert_eq!(games.len(), 1);
+    let game = &games[0];
+    assert_eq!(game.get_tag("White"), Some("Fischer, Robert J."));
+    assert_eq!(game.get_tag("ECO"), Some("C95"));
+    assert_eq!(game.result, Some(GameResult::Tie));
+    assert_eq!(game.mainline.moves.len(), 85);
+    assert_eq!(game.mainline.comments, vec!["Opening comment".to_string()]);
+    assert_eq!(game.mainline.moves[4].comments,
+        vec!["This opening is called the Ruy Lopez.".to_string()]);
+
+    let state = game.replay(HashChars::new([0; 32])).unwrap();
+    assert_eq!(state.to_fen(), "8/8/4R1p1/2k3p1/1p4P1/1P1b1P2/3K1n2/8 b - - 2 43");
+    assert!(roundtrip(game) == *game);
+}
+
+#[test]
+fn test_pgn_nags_and_variations() {
+    let text = "1. e4! e5 (1... c5!? 2. Nf3 (2. c3) d6 $14) (1... e6) 2. Nf3?? *";
+    let games = read_pgn(text).unwrap();
+    let game = &games[0];
+    assert_eq!(game.result, None);
+    let moves = &game.mainline.moves;
+    assert_eq!(moves.len(), 3);
+    assert_eq!(moves[0].nags, vec![1]);
+    assert_eq!(moves[2].nags, vec![4]);
+    assert_eq!(moves[1].variations.len(), 2);
+    let sicilian = &moves[1].variations[0];
+    assert_eq!(sicilian.moves.len(), 3);
+    assert_eq!(sicilian.moves[0].nags, vec![5]);
+    assert_eq!(sicilian.moves[1].variations[0].moves.len(), 1);
+    assert_eq!(sicilian.moves[2].nags, vec![14]);
+    // Placeholder values for the Seven Tag Roster are added on export.
+    let reread = roundtrip(game);
+    assert_eq!(reread.get_tag("Event"), Some("?"));
+    assert!(reread.mainline == game.mainline);
+    assert_eq!(reread.result, game.result);
+}
+
+#[test]
+fn test_pgn_setup_fen() {
+    let text = r#"[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+
+30... Kd7 31. e4 Kd6 *"#;
+    let games = read_pgn(text).unwrap();
+    let state = games[0].replay(HashChars::new([0; 32])).unwrap();
+    assert_eq!(state.to_fen(), "8/8/3k4/8/4P3/8/8/4K3 w - - 1 32");
+
+    // Exporting a game which began in a custom position preserves the position.
+    let exported = PgnGame::from_chess_game(&state);
+    assert_eq!(exported.get_tag("FEN"), Some("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"));
+    assert_eq!(exported.get_tag("SetUp"), Some("1"));
+    let mut text: Vec<u8> = Vec::new();
+    write_pgn(&mut text, &exported).unwrap();
+    assert!(String::from_utf8(text).unwrap().contains("30... Kd7 31. e4 Kd6 *"));
+}
+
+#[test]
+fn test_pgn_from_chess_game() {
+    let mut state = ChessGame::from_fen(chess_solver_3::fen::STARTING_FEN,
+        HashChars::new([0; 32])).unwrap();
+    for lan in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+        let mov = parse_lan(&mut state, lan).unwrap();
+        make_move(&mut state, mov);
+    }
+    let mut game = PgnGame::from_chess_game(&state);
+    game.set_tag("White", "Fool");
+    assert_eq!(game.result, Some(GameResult::Diff(Color::Black)));
+    assert_eq!(game.get_tag("FEN"), None);
+
+    let mut exported: Vec<u8> = Vec::new();
+    write_pgn(&mut exported, &game).unwrap();
+    let exported = String::from_utf8(exported).unwrap();
+    assert!(exported.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"));
+    assert!(exported.contains("[White \"Fool\"]\n"));
+    assert!(exported.contains("[Result \"0-1\"]\n"));
+    assert!(exported.contains("1. f3 e5 2. g4 Qh4# 0-1"));
+    let reread = roundtrip(&game);
+    assert!(reread.mainline == game.mainline);
+    assert_eq!(reread.get_tag("White"), Some("Fool"));
+}
+
+#[test]
+fn test_pgn_rejects_illegal_move() {
+    assert!(matches!(read_pgn("1. e4 e5 2. Ke3 *"), Err(ReadPgnErr::BadMove(_, _))));
+    assert!(matches!(read_pgn("1. e4 (1. d4"), Err(ReadPgnErr::UnbalancedVariation)));
+    assert!(matches!(read_pgn("1. e4 {unterminated"), Err(ReadPgnErr::UnterminatedComment)));
+}
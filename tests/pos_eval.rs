@@ -0,0 +1,36 @@
+mod common;
+
+use chess_solver_3::eval_params::EVAL_PARAMS;
+use chess_solver_3::pos_eval::{calc_phase, calc_positional_eval, calc_positional_score, MAX_PHASE};
+use common::{SPECIAL_MOVE_FENS, position, verify_incremental};
+
+#[test]
+fn test_positional_score_is_incremental() {
+    for fen in SPECIAL_MOVE_FENS {
+        verify_incremental(&mut position(fen), 2, &|state| {
+            assert_eq!(state.pos_score, calc_positional_score(&state.bbs, &EVAL_PARAMS));
+        });
+    }
+}
+
+#[test]
+fn test_positional_eval_is_symmetric() {
+    let initial = position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(calc_positional_eval(&initial), 0);
+    assert_eq!(calc_phase(&initial.bbs), MAX_PHASE);
+
+    // Mirroring the board vertically and swapping colors yields the same evaluation.
+    let white = position("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4");
+    let black = position("rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b KQkq - 4 4");
+    assert_eq!(calc_positional_eval(&white), calc_positional_eval(&black));
+    assert_ne!(calc_positional_eval(&white), 0);
+}
+
+#[test]
+fn test_positional_eval_tapers_toward_endgame() {
+    // A lone king is better in the center than in the corner, but only in the endgame.
+    let center = position("8/8/8/4k3/8/8/PPPP4/K7 b - - 0 1");
+    let corner = position("k7/8/8/8/8/8/PPPP4/K7 b - - 0 1");
+    assert_eq!(calc_phase(&center.bbs), 0);
+    assert!(calc_positional_eval(&center) > calc_positional_eval(&corner));
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use chess_solver_3::cache::Cache;
+use chess_solver_3::lan::format_lan;
+use chess_solver_3::misc::SegVec;
+use chess_solver_3::mov::{AnyMove, get_target_sq};
+use chess_solver_3::movegen::dispatch::{movegen_captures, movegen_legal};
+use chess_solver_3::movegen::types::GeneratedMove;
+use chess_solver_3::search::{IterDeepSearchContext, iterdeep_search};
+use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+use common::position;
+
+#[test]
+fn test_movegen_captures_is_noisy_subset() {
+    for fen in ["r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3"]
+    {
+        let mut state = position(fen);
+        let mover = state.active_player();
+        let mut legal: Vec<GeneratedMove> = Vec::new();
+        movegen_legal(&mut state, &mut legal);
+        let mut expected: Vec<String> = legal.iter()
+            .filter(|genmove| match genmove.mov {
+                AnyMove::Piece(pmove) => {
+                    let target = get_target_sq(pmove, &mut state);
+                    pmove.promote.is_some() || state.p_lut.get(target).is_some()
+                },
+                AnyMove::Castle(_) => false,
+            })
+            .map(|genmove| format_lan(genmove.mov, mover))
+            .collect();
+        let mut captures: Vec<GeneratedMove> = Vec::new();
+        movegen_captures(&mut state, &mut captures);
+        let mut actual: Vec<String> = captures.iter()
+            .map(|genmove| format_lan(genmove.mov, mover))
+            .collect();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected, "{}", fen);
+    }
+}
+
+#[test]
+fn test_quiescence_avoids_poisoned_capture() {
+    // Taking the pawn on d5 wins a pawn at the horizon but loses the queen to exd5.
+    let mut state = position("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1");
+    let cache = Cache::new(1);
+    let result = iterdeep_search(IterDeepSearchContext { gstate: &mut state,
+        movebuf: SegVec::new(&mut RefCell::default()),
+        deadline: Instant::now() + Duration::from_secs(60), cache: &cache,
+        stop: &AtomicBool::new(false), max_depth: 1, thread_count: 1,
+        time_manager: None, on_iteration: &mut |_| {} });
+    assert_eq!(result.depth_achieved, 1);
+    assert_ne!(format_lan(result.bestmove, state.active_player()), "d1d5");
+}
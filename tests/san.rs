@@ -0,0 +1,85 @@
+mod common;
+
+use chess_solver_3::fen::STARTING_FEN;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::makemove::make_move;
+use chess_solver_3::mov::AnyMove;
+use chess_solver_3::movegen::dispatch::movegen_legal;
+use chess_solver_3::movegen::types::GeneratedMove;
+use chess_solver_3::san::{ParseSanErr, format_san, parse_san};
+use common::position;
+
+fn san_of(state: &mut ChessGame, lan: &str) -> String {
+    let mov = parse_lan(state, lan).unwrap();
+    return format_san(state, mov);
+}
+
+#[test]
+fn test_san_format() {
+    let mut state = position(STARTING_FEN);
+    assert_eq!(san_of(&mut state, "e2e4"), "e4");
+    assert_eq!(san_of(&mut state, "g1f3"), "Nf3");
+
+    // Castling, captures and checks.
+    let mut state = position("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1");
+    assert_eq!(san_of(&mut state, "e1g1"), "O-O");
+    assert_eq!(san_of(&mut state, "e1c1"), "O-O-O");
+    assert_eq!(san_of(&mut state, "d5e6"), "dxe6");
+    assert_eq!(san_of(&mut state, "e5f7"), "Nxf7");
+    assert_eq!(san_of(&mut state, "e2a6"), "Bxa6");
+    let mut state = position("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+    assert_eq!(san_of(&mut state, "a1a8"), "Ra8+");
+
+    // Promotion and checkmate.
+    let mut state = position("k7/4P3/1K6/8/8/8/8/8 w - - 0 1");
+    assert_eq!(san_of(&mut state, "e7e8q"), "e8=Q#");
+    assert_eq!(san_of(&mut state, "e7e8n"), "e8=N");
+}
+
+#[test]
+fn test_san_disambiguation() {
+    // Knights on b1 and f1 share a rank, so the file disambiguates.
+    let mut state = position("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert_eq!(san_of(&mut state, "b1d2"), "Nbd2");
+    // Rooks on a1 and a5 share a file, so the rank disambiguates.
+    let mut state = position("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(san_of(&mut state, "a1a3"), "R1a3");
+    // The queen on h1 shares its file with one rival and its rank with the other.
+    let mut state = position("2k5/8/8/8/7Q/8/K7/4Q2Q w - - 0 1");
+    assert_eq!(san_of(&mut state, "h1e4"), "Qh1e4");
+}
+
+#[test]
+fn test_san_parse_roundtrip() {
+    for fen in [STARTING_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "2k5/8/8/8/7Q/8/K7/4Q2Q w - - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8"]
+    {
+        let mut state = position(fen);
+        let mut legal_moves: Vec<GeneratedMove> = Vec::new();
+        movegen_legal(&mut state, &mut legal_moves);
+        for genmove in legal_moves {
+            let san = format_san(&mut state, genmove.mov);
+            assert_eq!(parse_san(&mut state, &san).unwrap(), genmove.mov, "{}", san);
+        }
+    }
+}
+
+#[test]
+fn test_san_parse_errors() {
+    let mut state = position("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1");
+    assert!(matches!(parse_san(&mut state, "Nd2"), Err(ParseSanErr::AmbiguousMove(_))));
+    assert!(matches!(parse_san(&mut state, "Nd3"), Err(ParseSanErr::IllegalMove)));
+    assert!(matches!(parse_san(&mut state, "O-O"), Err(ParseSanErr::IllegalMove)));
+    assert!(matches!(parse_san(&mut state, ""), Err(ParseSanErr::Empty)));
+    assert!(matches!(parse_san(&mut state, "Zd2"), Err(ParseSanErr::MalformedSpecies('Z'))));
+    assert!(matches!(parse_san(&mut state, "Nd9"), Err(ParseSanErr::MalformedDestin(_))));
+
+    // Informal spellings are accepted.
+    let mut state = position(STARTING_FEN);
+    let e4 = parse_san(&mut state, "e4!").unwrap();
+    make_move(&mut state, e4);
+    assert!(matches!(parse_san(&mut state, "Nf6+?"), Ok(AnyMove::Piece(_))));
+}
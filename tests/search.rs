@@ -0,0 +1,112 @@
+use chess_solver_3::cache::{Cache, HashChars};
+use chess_solver_3::eval::{ABOVE_MAX_SCORE, BELOW_MIN_SCORE, DeepEvalContext, deep_eval};
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::makemove::{make_move, unmake_move};
+use chess_solver_3::misc::SegVec;
+use chess_solver_3::movegen::dispatch::movegen_legal;
+use chess_solver_3::movegen::types::GeneratedMove;
+use chess_solver_3::movesort::MoveHistory;
+use chess_solver_3::pawn_eval::PawnCache;
+use chess_solver_3::search::{IterDeepSearchContext, IterDeepSearchResult, iterdeep_search};
+use std::cell::RefCell;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/// A plain negamax search without any pruning, applying the same evaluation as
+/// the real search at the horizon.
+fn minimax(state: &mut ChessGame, depth: u8, ply: u8, cache: &Cache) -> i16 {
+    let mut moves: Vec<GeneratedMove> = Vec::new();
+    movegen_legal(state, &mut moves);
+    if (depth == 0) | moves.is_empty() {
+        let mut node_count: u64 = 0;
+        let score = deep_eval(DeepEvalContext { gstate: state, lookahead: 0, ply,
+            movebuf: SegVec::new(&mut RefCell::default()),
+            deadline: Instant::now() + Duration::from_secs(60), alpha: BELOW_MIN_SCORE,
+            beta: ABOVE_MAX_SCORE, cache, node_count: &mut node_count,
+            stop: &AtomicBool::new(false), history: &mut MoveHistory::new(),
+            pawn_cache: &mut PawnCache::new() });
+        return score.ok().unwrap();
+    }
+    let mut best = BELOW_MIN_SCORE;
+    for genmove in moves {
+        make_move(state, genmove.mov);
+        best = best.max(-1 * minimax(state, depth - 1, ply + 1, cache));
+        unmake_move(state);
+    }
+    return best;
+}
+
+#[test]
+fn test_alphabeta_matches_minimax() {
+    for fen in ["8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1"]
+    {
+        let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+        let cache = Cache::new(1);
+        let result = iterdeep_search(IterDeepSearchContext { gstate: &mut state,
+            movebuf: SegVec::new(&mut RefCell::default()),
+            deadline: Instant::now() + Duration::from_secs(60 * 60), cache: &cache,
+            stop: &AtomicBool::new(false), max_depth: 2, thread_count: 1,
+            time_manager: None, on_iteration: &mut |_| {} });
+        assert_eq!(result.depth_achieved, 2);
+        assert_eq!(result.score, minimax(&mut state, 2, 0, &cache), "{}", fen);
+    }
+}
+
+#[test]
+fn test_principal_variation() {
+    let fen = "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1";
+    let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let cache = Cache::new(1);
+    let result = iterdeep_search(IterDeepSearchContext { gstate: &mut state,
+        movebuf: SegVec::new(&mut RefCell::default()),
+        deadline: Instant::now() + Duration::from_secs(60 * 60), cache: &cache,
+        stop: &AtomicBool::new(false), max_depth: 3, thread_count: 1,
+        time_manager: None, on_iteration: &mut |_| {} });
+    assert_eq!(result.pv[0], result.bestmove);
+    assert!(result.pv.len() > 1);
+    assert!(result.pv.len() <= usize::from(result.depth_achieved));
+    // Each move of the line is legal in the position which precedes it.
+    for mov in &result.pv {
+        let mut moves: Vec<GeneratedMove> = Vec::new();
+        movegen_legal(&mut state, &mut moves);
+        assert!(moves.iter().any(|genmove| genmove.mov == *mov));
+        make_move(&mut state, *mov);
+    }
+    assert_eq!(state.movelog.len(), result.pv.len());
+}
+
+fn search_to_depth(fen: &str, max_depth: u8, thread_count: usize) -> IterDeepSearchResult {
+    let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let cache = Cache::new(1);
+    return iterdeep_search(IterDeepSearchContext { gstate: &mut state,
+        movebuf: SegVec::new(&mut RefCell::default()),
+        deadline: Instant::now() + Duration::from_secs(60 * 60), cache: &cache,
+        stop: &AtomicBool::new(false), max_depth, thread_count, time_manager: None,
+        on_iteration: &mut |_| {} });
+}
+
+#[test]
+fn test_mate_distance() {
+    // Rh8 only checks, as the king escapes to a7, but Kb6 forces mate next move.
+    let result = search_to_depth("k7/8/2K5/8/8/8/8/7R w - - 0 1", 5, 1);
+    assert_eq!(result.mate_in, Some(2));
+    let result = search_to_depth("k7/8/1K6/8/8/8/8/7R b - - 0 1", 4, 1);
+    assert_eq!(result.mate_in, Some(-1));
+    let result = search_to_depth("k7/8/1K6/8/8/8/8/7R w - - 0 1", 4, 1);
+    assert_eq!(result.mate_in, Some(1));
+    assert_eq!(result.pv.len(), 1);
+}
+
+#[test]
+fn test_lazy_smp_agrees_with_single_thread() {
+    // The helpers only share the cache, so the result is sound regardless of their timing.
+    let result = search_to_depth("k7/8/2K5/8/8/8/8/7R w - - 0 1", 5, 4);
+    assert_eq!(result.depth_achieved, 5);
+    assert_eq!(result.mate_in, Some(2));
+    let fen = "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4";
+    let result = search_to_depth(fen, 4, 4);
+    assert_eq!(result.mate_in, Some(1));
+    assert!(result.node_count > search_to_depth(fen, 1, 1).node_count);
+}
@@ -0,0 +1,36 @@
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::lan::parse_lan;
+use chess_solver_3::mov::AnyMove;
+use chess_solver_3::see::static_exchange_eval;
+
+fn see(fen: &str, lan: &str) -> i16 {
+    let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+    let AnyMove::Piece(pmove) = parse_lan(&mut state, lan).unwrap() else { panic!() };
+    return static_exchange_eval(&mut state, pmove);
+}
+
+#[test]
+fn test_see_simple_exchanges() {
+    // An undefended pawn is won outright, even by the queen.
+    assert_eq!(see("1k6/8/8/4p3/8/8/8/1K2Q3 w - - 0 1", "e1e5"), 1);
+    // A knight defended by a pawn costs the rook which captures it.
+    assert_eq!(see("1k6/8/5p2/4n3/8/8/8/1K2R3 w - - 0 1", "e1e5"), -2);
+    // Even trades are neutral.
+    assert_eq!(see("1k6/8/5p2/4p3/3P4/8/8/1K6 w - - 0 1", "d4e5"), 0);
+    // The king recaptures on an undefended square, but not on a defended one.
+    assert_eq!(see("8/8/3k4/4p3/8/8/8/1K2R3 w - - 0 1", "e1e5"), -4);
+    assert_eq!(see("8/8/3k4/4p3/8/8/7Q/1K2R3 w - - 0 1", "e1e5"), 1);
+}
+
+#[test]
+fn test_see_xray_and_promotion() {
+    // The rook behind the capturing rook recaptures once the path is clear.
+    assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/3R2K1 w - - 0 1", "d2d5"), 1);
+    assert_eq!(see("3r2k1/8/8/3p4/8/8/3R4/6K1 w - - 0 1", "d2d5"), -4);
+    // Known example: the knight takes a pawn defended by a knight and x-rayed by a queen.
+    assert_eq!(see("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1", "d3e5"), -2);
+    // A promotion which cannot be recaptured gains the difference of queen and pawn.
+    assert_eq!(see("1k6/4P3/8/8/8/8/8/1K6 w - - 0 1", "e7e8q"), 8);
+    assert_eq!(see("1k1r4/4P3/8/8/8/8/8/1K6 w - - 0 1", "e7d8q"), 5 + 8);
+}
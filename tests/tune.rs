@@ -0,0 +1,65 @@
+use chess_solver_3::cache::HashChars;
+use chess_solver_3::eval::{calc_static_eval, shallow_eval};
+use chess_solver_3::eval_params::EVAL_PARAMS;
+use chess_solver_3::gamestate::ChessGame;
+use chess_solver_3::pawn_eval::PawnCache;
+use chess_solver_3::tune::{calc_error, format_params_source, parse_labeled_position, tune};
+use chess_solver_3::tune::{LabeledPosition, ParseLabeledPositionErr};
+
+const POSITIONS: [&str; 6] = [
+    "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4 [0.5]",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1 [1.0]",
+    "3r2k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1 [0.0]",
+    "4k3/8/8/3P4/8/8/8/4K3 w - - 0 1 [1.0]",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - [0.5]",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 [0.0]",
+];
+
+fn training_set() -> Vec<LabeledPosition> {
+    let hash_ch = HashChars::new([0; 32]);
+    return POSITIONS.iter()
+        .map(|line| parse_labeled_position(line, &hash_ch).unwrap())
+        .collect();
+}
+
+#[test]
+fn test_eval_params_source_is_reproducible() {
+    assert_eq!(format_params_source(&EVAL_PARAMS), include_str!("../src/eval_params.rs"));
+}
+
+#[test]
+fn test_static_eval_matches_search_eval() {
+    let mut pawn_cache = PawnCache::new();
+    for line in POSITIONS {
+        let fen = line.rsplit_once(' ').unwrap().0;
+        let mut state = ChessGame::from_fen(fen, HashChars::new([0; 32])).unwrap();
+        let expected = calc_static_eval(&state.bbs, &state.has_castled, &EVAL_PARAMS);
+        assert_eq!(shallow_eval(&mut state, 0, &mut pawn_cache), expected, "{}", fen);
+    }
+}
+
+#[test]
+fn test_parse_labeled_position() {
+    let hash_ch = HashChars::new([0; 32]);
+    let parse = |line: &str| parse_labeled_position(line, &hash_ch).map(|p| p.result);
+    assert_eq!(parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1 [1.0]").unwrap(), 1.0);
+    assert_eq!(parse("4k3/8/8/8/8/8/8/4K3 w - - c9 \"1/2-1/2\";").unwrap(), 0.5);
+    assert_eq!(parse("4k3/8/8/8/8/8/8/4K3 b - - 12 40 0-1").unwrap(), 0.0);
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/4K3 w - - 0 1"),
+        Err(ParseLabeledPositionErr::MissingResult)));
+    assert!(matches!(parse("4k3/8/8/8/8/8/8/8 w - - 0 1 1-0"),
+        Err(ParseLabeledPositionErr::MalformedFen(_))));
+}
+
+#[test]
+fn test_tune_reduces_error() {
+    let positions = training_set();
+    let mut params = EVAL_PARAMS.clone();
+    let initial = calc_error(&positions, &params, 1.0);
+    let mut passes: u32 = 0;
+    let tuned = tune(&positions, &mut params, 1.0, 1, &mut |_, _, _| passes += 1);
+    assert_eq!(passes, 1);
+    assert!(tuned < initial);
+    assert_eq!(tuned, calc_error(&positions, &params, 1.0));
+    assert_ne!(params, EVAL_PARAMS);
+}